@@ -0,0 +1,170 @@
+//! `#[derive(JtlObject)]`, enabled with the "derive" feature of `jtl_runtime`.
+//!
+//! Turns a struct with named fields into a template object, and describes it to the schema
+//! of the language server so both are made out of the same definition.
+//!
+//! ```ignore
+//! #[derive(JtlObject)]
+//! #[jtl(description = "A member of a guild")]
+//! struct Member {
+//!     #[jtl(rename = "displayName", description = "Nickname, or username without one")]
+//!     display_name: String,
+//!     #[jtl(skip)]
+//!     token: String,
+//! }
+//! ```
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, parse_quote, Attribute, Data, DeriveInput, Fields, LitStr};
+
+#[proc_macro_derive(JtlObject, attributes(jtl))]
+pub fn derive_jtl_object(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// What `#[jtl(...)]` says about a struct or a field
+#[derive(Default)]
+struct Options {
+    rename: Option<String>,
+    description: Option<String>,
+    skip: bool,
+}
+
+impl Options {
+    fn from_attributes(attributes: &[Attribute]) -> syn::Result<Self> {
+        let mut options = Options::default();
+        for attribute in attributes.iter().filter(|attr| attr.path().is_ident("jtl")) {
+            attribute.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    options.rename = Some(meta.value()?.parse::<LitStr>()?.value());
+                } else if meta.path.is_ident("description") {
+                    options.description = Some(meta.value()?.parse::<LitStr>()?.value());
+                } else if meta.path.is_ident("skip") {
+                    options.skip = true;
+                } else {
+                    return Err(meta.error("expected `rename`, `description` or `skip`"));
+                }
+                Ok(())
+            })?;
+        }
+
+        Ok(options)
+    }
+}
+
+fn description(description: &Option<String>) -> TokenStream2 {
+    match description {
+        Some(description) => quote!(::std::option::Option::Some(#description)),
+        None => quote!(::std::option::Option::None),
+    }
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "JtlObject can only be derived for structs",
+        ));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "JtlObject can only be derived for structs with named fields",
+        ));
+    };
+
+    let container = Options::from_attributes(&input.attrs)?;
+    if container.skip {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "`skip` is only for fields",
+        ));
+    }
+    let ident = &input.ident;
+    let name = container.rename.unwrap_or_else(|| ident.to_string());
+    let object_description = description(&container.description);
+
+    let mut inserts = Vec::new();
+    let mut schema_fields = Vec::new();
+    let mut field_types = Vec::new();
+    for field in &fields.named {
+        let options = Options::from_attributes(&field.attrs)?;
+        if options.skip {
+            continue;
+        }
+        let field_ident = field.ident.as_ref().expect("named fields have an ident");
+        let field_name = options.rename.unwrap_or_else(|| field_ident.to_string());
+        let field_type = &field.ty;
+        let field_description = description(&options.description);
+
+        inserts.push(quote! {
+            object.insert(
+                ::std::string::String::from(#field_name),
+                ::jtl_runtime::runtime::Value::from(self.#field_ident),
+            );
+        });
+        schema_fields.push(quote! {
+            ::jtl_runtime::object::FieldSchema {
+                name: #field_name,
+                description: #field_description,
+                type_name: <#field_type as ::jtl_runtime::function::TypeName>::type_name(),
+            }
+        });
+        field_types.push(field_type);
+    }
+
+    // A field of a generic type is only a value with a type name for some types, bound each field
+    let mut generics = input.generics.clone();
+    if !generics.params.is_empty() {
+        let predicates = &mut generics.make_where_clause().predicates;
+        for field_type in &field_types {
+            predicates.push(
+                parse_quote!(::jtl_runtime::runtime::Value: ::std::convert::From<#field_type>),
+            );
+            predicates.push(parse_quote!(#field_type: ::jtl_runtime::function::TypeName));
+        }
+    }
+    let (impl_generics, type_generics, where_clause) = generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::jtl_runtime::object::JtlObject for #ident #type_generics #where_clause {
+            fn into_object(self) -> ::jtl_runtime::runtime::ObjectHashMap {
+                let mut object = ::jtl_runtime::runtime::ObjectHashMap::new();
+                #(#inserts)*
+                object
+            }
+        }
+
+        impl #impl_generics ::std::convert::From<#ident #type_generics> for ::jtl_runtime::runtime::Value #where_clause {
+            fn from(object: #ident #type_generics) -> Self {
+                ::jtl_runtime::runtime::Value::Object(
+                    ::jtl_runtime::object::JtlObject::into_object(object),
+                )
+            }
+        }
+
+        impl #impl_generics ::jtl_runtime::function::TypeName for #ident #type_generics #where_clause {
+            fn type_name() -> ::std::string::String {
+                ::std::format!("#{}", #name)
+            }
+
+            fn objects(objects: &mut ::std::vec::Vec<::jtl_runtime::object::ObjectSchema>) {
+                // Already described, aka: a struct with a field of its own type
+                if objects.iter().any(|object| object.name == #name) {
+                    return;
+                }
+                objects.push(::jtl_runtime::object::ObjectSchema {
+                    name: #name,
+                    description: #object_description,
+                    fields: ::std::vec![#(#schema_fields),*],
+                });
+                #(<#field_types as ::jtl_runtime::function::TypeName>::objects(objects);)*
+            }
+        }
+    })
+}
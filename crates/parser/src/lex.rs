@@ -1,27 +1,40 @@
 use super::utils;
+use std::fmt;
 
-#[derive(Debug, Clone, PartialEq)]
-pub enum TToken {
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TToken<'src> {
     WS,
-    Text(String),
+    Text(&'src str),
     OpenTag,
-    Ident(String),
+    Ident(&'src str),
     // 9999999999999999999 - u64 max
     // 999999999 - u32 max
     Int(u32),
     // Bool(bool),
-    String(String),
+    String(&'src str),
     Dot,
     ArgumentInitalizer,
     ArgumentSeperator,
+    /// '!' at the start of a tag
+    Raw,
     CloseTag,
 }
 
-#[derive(Debug, Clone)]
-pub struct Token {
-    pub token: TToken,
+/// Byte range of a token within the source it was lexed from.
+///
+/// `&source[span.start..span.end]` is the raw text of the token (quotes included for strings).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Token<'src> {
+    pub token: TToken<'src>,
     pub start: PPosition,
     pub end: PPosition,
+    pub span: Span,
 }
 
 /// Position(column, line)
@@ -44,7 +57,7 @@ pub struct Token {
 //     }
 // }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct PPosition {
     pub column: usize,
     pub line: usize,
@@ -57,9 +70,6 @@ impl PPosition {
     fn advance_line(&mut self) {
         self.line += 1
     }
-    fn back_line(&mut self) {
-        self.line -= 1
-    }
     // pub fn to_offset() -> usize {}
     #[deprecated]
     pub fn to_pointer(&self) -> usize {
@@ -67,195 +77,211 @@ impl PPosition {
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct LexError {
+    pub message: String,
+    pub start: PPosition,
+    pub end: PPosition,
+    pub span: Span,
+}
+
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for LexError {}
+
+/// Tokenizer over a borrowed source.
+///
+/// Tokens reference the source directly (`TToken::Text`, `TToken::Ident` and `TToken::String`
+/// are slices of it), so scanning does not allocate beyond the token buffer itself.
+///
+/// The lexer is an iterator of tokens, so they can be consumed lazily without collecting them
+/// into `tokens` first. Iteration carries on past an error, resuming after the offending input.
 #[derive(Debug)]
-pub struct Lexer {
-    pub tokens: Vec<Token>,
+pub struct Lexer<'src> {
+    pub tokens: Vec<Token<'src>>,
+    /// Byte offset of the next character to be read
     pub pointer: usize,
-    source_chars: Vec<char>,
+    source: &'src str,
     pub position: PPosition,
     pub is_text: bool,
 }
 
-impl Lexer {
-    pub fn from_source(program: &str) -> Self {
+impl<'src> Lexer<'src> {
+    pub fn from_source(program: &'src str) -> Self {
         Lexer {
             tokens: Vec::new(),
             pointer: 0,
-            source_chars: program.chars().collect(),
+            source: program,
             position: PPosition { column: 0, line: 0 },
             is_text: true,
         }
     }
 
-    // TODO: Show position when erroring
-    pub fn scan_tokens(&mut self) -> Result<(), String> {
-        while !self.is_at_end() {
-            let char = self.advance();
-            let start = self.position.clone();
-            // self.position.advance_line();
-
-            let token: Option<TToken> = {
-                if let Some(char) = char {
-                    match (self.is_text, char) {
-                        (_, ' ' | '\r' | '\t') => {
-                            self.position.advance_line();
-                            Some(TToken::WS)
-                        }
-                        (_, '\n') => {
-                            self.position.advance_column();
-                            Some(TToken::WS)
-                        }
-                        (_, '{') => {
-                            self.is_text = false;
-                            self.position.advance_line();
-                            Some(TToken::OpenTag)
-                        }
-                        (_, '}') => {
-                            self.is_text = true;
-                            self.position.advance_line();
-                            Some(TToken::CloseTag)
-                        }
+    pub fn source(&self) -> &'src str {
+        self.source
+    }
 
-                        (false, '.') => {
-                            self.position.advance_line();
-                            Some(TToken::Dot)
-                        }
-                        (false, ';') => {
-                            self.position.advance_line();
-                            Some(TToken::ArgumentSeperator)
-                        }
-                        (false, '|') => {
+    /// Scans the whole source into `self.tokens`, stopping at the first error.
+    ///
+    /// Prefer iterating the lexer directly when the tokens don't all need to be held at once.
+    pub fn scan_tokens(&mut self) -> Result<(), LexError> {
+        while let Some(token) = self.next_token() {
+            self.tokens.push(token?);
+        }
+
+        Ok(())
+    }
+
+    fn next_token(&mut self) -> Option<Result<Token<'src>, LexError>> {
+        let char = self.advance()?;
+        let start = self.position;
+        let start_offset = self.pointer - char.len_utf8();
+
+        let token: TToken<'src> = match (self.is_text, char) {
+            (_, ' ' | '\r' | '\t') => {
+                self.position.advance_line();
+                TToken::WS
+            }
+            (_, '\n') => {
+                self.position.advance_column();
+                TToken::WS
+            }
+            (_, '{') => {
+                self.is_text = false;
+                self.position.advance_line();
+                TToken::OpenTag
+            }
+            (_, '}') => {
+                self.is_text = true;
+                self.position.advance_line();
+                TToken::CloseTag
+            }
+
+            (false, '.') => {
+                self.position.advance_line();
+                TToken::Dot
+            }
+            (false, ';') => {
+                self.position.advance_line();
+                TToken::ArgumentSeperator
+            }
+            (false, '|') => {
+                self.position.advance_line();
+                TToken::ArgumentInitalizer
+            }
+            (false, '!') => {
+                self.position.advance_line();
+                TToken::Raw
+            }
+            // TODO: escapeable string
+            (false, '"') => {
+                self.position.advance_line();
+                loop {
+                    match self.advance() {
+                        Some('"') => {
                             self.position.advance_line();
-                            Some(TToken::ArgumentInitalizer)
+                            break;
                         }
-                        // TODO: escapeable string
-                        (false, '"') => {
-                            self.position.advance_line();
-                            let start = self.pointer.clone() - 1;
-                            loop {
-                                self.position.advance_line();
-                                match self.advance() {
-                                    Some('"') => break,
-                                    Some(_) => {}
-                                    None => return Err("Unterminated string".to_string()),
-                                }
-                            }
-                            Some(TToken::String(
-                                // Remove starting '"' and end '"'
-                                self.source_chars[(start + 1)..(self.pointer - 1)]
-                                    .into_iter()
-                                    .collect(),
-                            ))
+                        Some(_) => self.position.advance_line(),
+                        None => {
+                            return Some(Err(self.error(
+                                "Unterminated string",
+                                start,
+                                start_offset,
+                            )))
                         }
+                    }
+                }
+                // Remove starting '"' and end '"'
+                TToken::String(&self.source[(start_offset + 1)..(self.pointer - 1)])
+            }
 
-                        (true, text) => {
-                            let mut content = String::new();
-                            self.position.advance_line();
+            (true, _) => {
+                self.position.advance_line();
+                while let Some(next) = self.peek() {
+                    if next == '{' || next == '\n' {
+                        break;
+                    }
+                    self.advance();
+                    self.position.advance_line();
+                }
 
-                            let mut chr: Option<char> = Some(text);
-                            while chr.is_some() {
-                                let x = chr.unwrap().to_string();
-                                content += &x;
-                                if self.peek() == Some('{') {
-                                    break;
-                                }
-                                if self.peek() == Some('\n') {
-                                    break;
-                                }
-                                chr = self.advance();
-                                self.position.advance_line();
-                            }
-
-                            Some(TToken::Text(content))
-                        }
+                TToken::Text(&self.source[start_offset..self.pointer])
+            }
 
-                        (false, ch) => {
-                            self.position.advance_line();
-                            let start = self.pointer.clone() - 1;
-                            if utils::is_alpha(ch) {
-                                loop {
-                                    self.position.advance_line();
-                                    match self.advance() {
-                                        Some(char) => {
-                                            if !utils::is_alpha(char) {
-                                                break;
-                                            }
-                                            continue;
-                                        }
-                                        None => break,
-                                    }
-                                }
-                                self.position.back_line();
-                                self.pointer -= 1;
-
-                                let ident =
-                                    self.source_chars[start..self.pointer].into_iter().collect();
-                                Some(TToken::Ident(ident))
-                            } else if utils::is_digit(ch) {
-                                loop {
-                                    self.position.advance_line();
-                                    match self.advance() {
-                                        Some(char) => {
-                                            if !utils::is_digit(char) {
-                                                break;
-                                            }
-                                            continue;
-                                        }
-                                        None => break,
-                                    }
-                                }
-                                self.position.back_line();
-                                self.pointer -= 1;
-
-                                let integer_res = (self.source_chars[start..self.pointer]
-                                    .into_iter()
-                                    .collect::<String>())
-                                .parse::<u32>();
-
-                                if integer_res.is_ok() {
-                                    Some(TToken::Int(integer_res.unwrap()))
-                                } else {
-                                    return Err(integer_res.err().unwrap().to_string());
-                                }
-                            } else {
-                                return Err("Unexpected token".to_string());
-                            }
+            (false, ch) => {
+                self.position.advance_line();
+                if utils::is_alpha(ch) {
+                    self.advance_while(utils::is_alpha_numeric);
+                    TToken::Ident(&self.source[start_offset..self.pointer])
+                } else if utils::is_digit(ch) {
+                    self.advance_while(utils::is_digit);
+                    match self.source[start_offset..self.pointer].parse::<u32>() {
+                        Ok(int) => TToken::Int(int),
+                        Err(err) => {
+                            return Some(Err(self.error(&err.to_string(), start, start_offset)))
                         }
                     }
                 } else {
-                    None
+                    return Some(Err(self.error("Unexpected token", start, start_offset)));
                 }
-            };
-
-            // println!("{:?}", token);
-            if token.is_some() {
-                self.tokens.push(Token {
-                    token: token.unwrap(),
-                    start,
-                    end: self.position.clone(),
-                })
             }
+        };
+
+        Some(Ok(Token {
+            token,
+            start,
+            end: self.position,
+            span: Span {
+                start: start_offset,
+                end: self.pointer,
+            },
+        }))
+    }
+
+    fn error(&self, message: &str, start: PPosition, start_offset: usize) -> LexError {
+        LexError {
+            message: message.to_string(),
+            start,
+            end: self.position,
+            span: Span {
+                start: start_offset,
+                end: self.pointer,
+            },
         }
+    }
 
-        Ok(())
+    /// Consumes characters for as long as `predicate` holds, advancing the position for each one
+    fn advance_while(&mut self, predicate: fn(char) -> bool) {
+        while let Some(char) = self.peek() {
+            if !predicate(char) {
+                break;
+            }
+            self.advance();
+            self.position.advance_line();
+        }
     }
 
     fn advance(&mut self) -> Option<char> {
-        let char = self.peek();
-        self.pointer += 1;
-        char
+        let char = self.peek()?;
+        self.pointer += char.len_utf8();
+        Some(char)
     }
 
     fn peek(&self) -> Option<char> {
-        if self.is_at_end() {
-            None
-        } else {
-            Some(self.source_chars[self.pointer])
-        }
+        self.source[self.pointer..].chars().next()
     }
+}
+
+impl<'src> Iterator for Lexer<'src> {
+    type Item = Result<Token<'src>, LexError>;
 
-    fn is_at_end(&self) -> bool {
-        self.pointer >= self.source_chars.len()
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_token()
     }
 }
 
@@ -277,4 +303,72 @@ ToPlacement|guild.count}",
         println!("{:#?}", res);
         println!("{:#?}", lex);
     }
+
+    #[test]
+    fn tokens_borrow_source() {
+        let source = "Hi {guild.name | \"world\"}";
+        let mut lex = Lexer::from_source(source);
+        lex.scan_tokens().unwrap();
+
+        for token in &lex.tokens {
+            let slice = &source[token.span.start..token.span.end];
+            match token.token {
+                TToken::Text(text) | TToken::Ident(text) => assert_eq!(text, slice),
+                TToken::String(text) => assert_eq!(format!("\"{}\"", text), slice),
+                _ => {}
+            }
+        }
+
+        let idents: Vec<_> = lex
+            .tokens
+            .iter()
+            .filter_map(|t| match t.token {
+                TToken::Ident(ident) => Some(ident),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(idents, vec!["guild", "name"]);
+    }
+
+    #[test]
+    fn unicode_idents() {
+        let source = "é {données.user2 | $x}";
+        let mut lex = Lexer::from_source(source);
+        lex.scan_tokens().unwrap();
+
+        let idents: Vec<_> = lex
+            .tokens
+            .iter()
+            .filter(|t| matches!(t.token, TToken::Ident(_)))
+            .collect();
+        assert_eq!(idents.len(), 3);
+
+        let donnees = idents[0];
+        assert_eq!(donnees.token, TToken::Ident("données"));
+        assert_eq!(&source[donnees.span.start..donnees.span.end], "données");
+        // Positions count characters, spans count bytes
+        assert_eq!((donnees.start.line, donnees.end.line), (3, 10));
+        assert_eq!((donnees.span.start, donnees.span.end), (4, 12));
+
+        assert_eq!(idents[1].token, TToken::Ident("user2"));
+        assert_eq!(idents[2].token, TToken::Ident("$x"));
+    }
+
+    #[test]
+    fn lazy_tokens_recover_after_error() {
+        let lex = Lexer::from_source("{a # b}");
+        let tokens: Vec<_> = lex.collect();
+
+        assert_eq!(tokens.len(), 7);
+        let err = tokens[3].as_ref().unwrap_err();
+        assert_eq!(err.message, "Unexpected token");
+        assert_eq!((err.span.start, err.span.end), (3, 4));
+        assert!(matches!(
+            tokens[5],
+            Ok(Token {
+                token: TToken::Ident("b"),
+                ..
+            })
+        ));
+    }
 }
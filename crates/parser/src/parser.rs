@@ -1,4 +1,5 @@
-use crate::lex::{Lexer, PPosition, TToken, Token};
+use crate::lex::{LexError, Lexer, PPosition, Span, TToken, Token};
+use std::collections::VecDeque;
 
 #[derive(Debug)]
 pub struct Source {
@@ -15,13 +16,18 @@ pub enum Stat {
         value: String,
         start: PPosition,
         end: PPosition,
+        span: Span,
     },
     Tag {
         _type: String,
         start: PPosition,
         end: PPosition,
+        /// Braces included
+        span: Span,
         value: Expression,
     },
+    /// Stray tokens outside of a tag, skipped with an error. Aka: the second '}' of "{a}}"
+    Error(ErrorNode),
 }
 
 #[derive(Debug, Clone)]
@@ -29,8 +35,14 @@ pub struct Expression {
     pub _type: String,
     pub start: PPosition,
     pub end: PPosition,
-    pub property: Option<PValue>,
+    pub property: PValue,
     pub arguments: Option<Vec<Arg>>,
+    /// Marked with a leading '!', its value is written without being escaped. Aka: "{!user.bio}"
+    pub raw: bool,
+    /// Tokens between the property and the arguments that were skipped while recovering.
+    ///
+    /// "{guild.name foo | ...}" -> ["foo"]
+    pub skipped: Vec<ErrorNode>,
 }
 
 #[derive(Debug, Clone)]
@@ -55,6 +67,14 @@ pub enum PValue {
     //     end: usize,
     //     value: bool,
     // },
+    /// Where a value was expected but there was none. Aka: "{}"
+    Missing {
+        _type: String,
+        start: PPosition,
+        end: PPosition,
+    },
+    /// Tokens that could not be parsed as a value. Aka: "{"guild"}"
+    Error(ErrorNode),
 }
 
 #[derive(Debug, Clone)]
@@ -71,6 +91,8 @@ pub struct Property {
 #[derive(Debug, Clone)]
 pub enum Arg {
     Single(Argument),
+    /// Tokens skipped within the arguments, kept in place so the argument order is preserved
+    Error(ErrorNode),
     //// Valid -> { toPlacement | (toInt | guild.count ; 0) ; false }
     // Group(Expression),
 }
@@ -85,6 +107,17 @@ pub struct Argument {
     pub end: PPosition,
 }
 
+/// Tokens the parser skipped while recovering from an error.
+///
+/// The matching `ParserError` holds the message, this keeps the range in the tree itself.
+#[derive(Debug, Clone)]
+pub struct ErrorNode {
+    pub _type: String,
+    pub start: PPosition,
+    pub end: PPosition,
+    pub span: Span,
+}
+
 #[derive(Debug)]
 pub struct ParserError {
     pub message: String,
@@ -92,25 +125,43 @@ pub struct ParserError {
     pub end: PPosition,
 }
 
+impl From<LexError> for ParserError {
+    fn from(err: LexError) -> Self {
+        ParserError {
+            message: err.message,
+            start: err.start,
+            end: err.end,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct ParserResults {
     pub errors: Vec<ParserError>,
     pub ast: Source,
 }
 
-pub struct Parser {
-    // errors: vec![],
-    tokens: Vec<Token>,
-    pointer: usize,
-    end_position: PPosition,
+/// Parses tokens as they are pulled from the lexer.
+///
+/// Only the lookahead is buffered, so a template never has to be fully tokenized up front.
+/// Tokens already scanned into `Lexer::tokens` are consumed first.
+pub struct Parser<'src> {
+    lexer: Lexer<'src>,
+    /// Tokens pulled from the lexer but not consumed yet
+    lookahead: VecDeque<Token<'src>>,
+    /// The last token consumed by 'advance'
+    previous: Option<Token<'src>>,
+    /// Errors yielded by the lexer while pulling tokens
+    lex_errors: Vec<ParserError>,
 }
 
-impl Parser {
-    pub fn from_lexer(lex: Lexer) -> Self {
+impl<'src> Parser<'src> {
+    pub fn from_lexer(mut lex: Lexer<'src>) -> Self {
         Parser {
-            pointer: 0,
-            tokens: lex.tokens,
-            end_position: lex.position,
+            lookahead: std::mem::take(&mut lex.tokens).into(),
+            lexer: lex,
+            previous: None,
+            lex_errors: Vec::new(),
         }
     }
 
@@ -118,18 +169,22 @@ impl Parser {
         let mut errors = Vec::new();
         let body = self.body(&mut errors);
 
+        // Lexer errors are only known once the lexer got to them, order them with the rest
+        errors.append(&mut self.lex_errors);
+        errors.sort_by_key(|err| (err.start.column, err.start.line));
+
         ParserResults {
             errors,
             ast: Source {
                 _type: "Source".to_string(),
                 start: PPosition { column: 0, line: 0 },
-                end: self.end_position.clone(),
+                end: self.lexer.position,
                 body,
             },
         }
     }
 
-    fn body(&mut self, mut errors: &mut Vec<ParserError>) -> Vec<Stat> {
+    fn body(&mut self, errors: &mut Vec<ParserError>) -> Vec<Stat> {
         let mut body = Vec::new();
 
         while !self.is_at_end() {
@@ -138,26 +193,24 @@ impl Parser {
                 match token_data.token {
                     TToken::Text(text) => body.push(Stat::Text {
                         _type: "Text".to_string(),
-                        value: text,
+                        value: text.to_string(),
                         start: token_data.start,
                         end: token_data.end,
+                        span: token_data.span,
                     }),
 
                     TToken::OpenTag => {
-                        let tag = self.tag_expression(&mut errors);
-                        let closing_token_end = {
-                            if !self.is_at_end() {
-                                self.tokens[self.pointer - 1].end.clone()
-                            } else {
-                                // AKA last token, I think...
-                                self.tokens[self.tokens.len() - 1].end.clone()
-                            }
-                        };
+                        let tag = self.tag_expression(errors);
+                        let closing_token = self.previous_token();
 
                         body.push(Stat::Tag {
                             _type: "Tag".to_string(),
                             start: token_data.start,
-                            end: closing_token_end,
+                            end: closing_token.end,
+                            span: Span {
+                                start: token_data.span.start,
+                                end: closing_token.span.end,
+                            },
                             value: tag,
                         })
                     }
@@ -167,8 +220,23 @@ impl Parser {
                     //     start: token_data.start,
                     //     end: token_data.end,
                     // }),
-                    t => {
-                        println!("TODO: {:?}", t);
+                    // Stray tokens outside of a tag. Aka: a lone '}' or what is left of a tag that failed to close
+                    _ => {
+                        let mut end = token_data.end;
+                        while let Some(next) = self.peek() {
+                            if matches!(next.token, TToken::Text(_) | TToken::OpenTag) {
+                                break;
+                            }
+                            end = next.end;
+                            self.advance();
+                        }
+
+                        errors.push(ParserError {
+                            message: "Unexpected Token".to_string(),
+                            start: token_data.start,
+                            end,
+                        });
+                        body.push(Stat::Error(self.error_node(&token_data)));
                     }
                 }
             }
@@ -177,12 +245,17 @@ impl Parser {
         body
     }
 
-    fn tag_expression(&mut self, mut errors: &mut Vec<ParserError>) -> Expression {
-        let start = self.tokens[self.pointer - 1].clone();
-        let property = self.tag_property(&mut errors);
+    fn tag_expression(&mut self, errors: &mut Vec<ParserError>) -> Expression {
+        let start = self.previous_token();
+        let raw = matches!(self.peek().map(|t| t.token), Some(TToken::Raw));
+        if raw {
+            self.advance();
+        }
+        let mut skipped = Vec::new();
+        let property = self.tag_property(errors, &mut skipped);
 
-        let arguments = self.tag_arguments(&mut errors);
-        let last_token = self.tokens[self.pointer - 1].clone();
+        let arguments = self.tag_arguments(errors);
+        let last_token = self.previous_token();
 
         let exp = Expression {
             _type: "Expression".to_string(),
@@ -190,30 +263,35 @@ impl Parser {
             end: last_token.end,
             property,
             arguments,
+            raw,
+            skipped,
         };
 
         // Hello {guild
-        let next = self.advance();
+        let next = self.peek().copied();
         if next.is_none() {
-            let last_token = self.tokens[self.tokens.len() - 1].clone();
+            let last_token = self.previous_token();
             errors.push(ParserError {
                 message: "Unexpected EOF expected '}'".to_string(),
-                start: last_token.end.clone(),
-                end: last_token.end.clone(),
+                start: last_token.end,
+                end: last_token.end,
             });
             return exp;
         }
 
         match next.unwrap().token {
-            TToken::CloseTag => {}
+            TToken::CloseTag => {
+                self.advance();
+            }
             // {toString | 0 world
             //              ^ forgot to close tag but not the end of the file
+            // The rest is left to 'body', which may be the start of the next tag
             _ => {
-                let last_token = self.tokens[self.tokens.len() - 1].clone(); // May be a bug idk. need testing later
+                let last_token = self.previous_token();
                 errors.push(ParserError {
                     message: "Expected '}'".to_string(),
-                    start: last_token.end.clone(),
-                    end: last_token.end.clone(),
+                    start: last_token.end,
+                    end: last_token.end,
                 });
             }
         }
@@ -221,185 +299,207 @@ impl Parser {
         exp
     }
 
-    fn tag_property(&mut self, errors: &mut Vec<ParserError>) -> Option<PValue> {
-        let peek_res = self.peek();
+    fn tag_property(
+        &mut self,
+        errors: &mut Vec<ParserError>,
+        skipped: &mut Vec<ErrorNode>,
+    ) -> PValue {
+        let peek_res = self.peek().copied();
         if peek_res.is_none() {
-            let last_token = self.tokens[self.tokens.len() - 1].clone();
+            let last_token = self.previous_token();
             errors.push(ParserError {
                 message: "Unexpected EOF expected Property".to_string(),
-                start: last_token.end.clone(),
-                end: last_token.end.clone(),
+                start: last_token.end,
+                end: last_token.end,
             });
-            return None;
+            return PValue::Missing {
+                _type: "Missing".to_string(),
+                start: last_token.end,
+                end: last_token.end,
+            };
         }
-        self.advance();
         let propery_init_token = peek_res.unwrap();
 
         // Ex: data.guild.meta.name
-        if let TToken::Ident(ident) = propery_init_token.token {
-            let mut idents = Vec::new();
-            idents.push(ident);
-
-            let next = self.peek();
-            if next.is_none() {
-                // NOTE: Should be an error of unclosed or unexpected EOF which is hanndled by 'tag_expression'
-                return None;
-            }
-            let next_token_data = next.unwrap();
-
-            match next_token_data.token {
-                TToken::Dot => {
-                    self.advance();
+        match propery_init_token.token {
+            TToken::Ident(ident) => {
+                self.advance();
+                let mut idents = Vec::new();
+                idents.push(ident.to_string());
 
-                    let mut last_was_dot = true;
-                    while !self.is_at_end() {
-                        let token_data = self.peek();
-                        if let Some(token_safe) = token_data {
-                            match token_safe.token {
-                                TToken::Dot => {
-                                    if last_was_dot {
-                                        errors.push(ParserError {
-                                            message: "Unexpected '.'".to_string(),
-                                            start: token_safe.start,
-                                            end: token_safe.end,
-                                        });
-                                    } else {
-                                        last_was_dot = true;
-                                    }
-                                    self.advance();
-                                }
-                                TToken::ArgumentSeperator | TToken::Int(_) => {
-                                    errors.push(ParserError {
-                                        message: "Unexpected Token".to_string(),
-                                        start: token_safe.start,
-                                        end: token_safe.end,
-                                    });
-                                    self.advance();
-                                }
-                                TToken::Ident(idnt) => {
-                                    self.advance();
-                                    if !last_was_dot {
-                                        // // Unsure if we should have the same recovery behaver as with the first instance of just skiping...
-                                        let end_token = self.advance_until(vec![
-                                            TToken::ArgumentInitalizer,
-                                            TToken::CloseTag,
-                                        ]);
-
-                                        let end_position = {
-                                            if end_token.is_some() {
-                                                end_token.unwrap().end
-                                            } else {
-                                                token_safe.end
-                                            }
-                                        };
-
-                                        errors.push(ParserError {
-                                            message: "Unexpected Token".to_string(),
-                                            start: token_safe.start,
-                                            end: end_position,
-                                        });
-                                    } else {
-                                        idents.push(idnt);
-                                        last_was_dot = false;
-                                    }
-                                }
-                                // {idnt.something ""} -> not allowed
-                                TToken::String(_) => {
-                                    self.advance();
-                                    errors.push(ParserError {
-                                        message: "Unexpected String".to_string(),
-                                        start: token_safe.start,
-                                        end: token_safe.end,
-                                    })
-                                }
-                                TToken::WS | TToken::OpenTag | TToken::Text(_) => {}
-                                TToken::ArgumentInitalizer | TToken::CloseTag => break, //  _ => break,
-                            };
-                        } else {
-                            break;
+                if let Some(next_token_data) = self.peek().copied() {
+                    self.tag_property_rest(next_token_data, &mut idents, errors, skipped);
+                }
+                // else: an error of unclosed or unexpected EOF which is hanndled by 'tag_expression'
+
+                let property_last_token = self.previous_token();
+
+                // When the last token is a closing it should be the token's 'start-1' otherwise it should be 'end'
+                let end = {
+                    match property_last_token.token {
+                        TToken::CloseTag => {
+                            let st = property_last_token.start;
+                            PPosition {
+                                column: st.column,
+                                line: st.line.saturating_sub(1),
+                            }
                         }
+                        _ => property_last_token.end,
                     }
+                };
 
-                    // Aka: {Idnt.} - no follow up was provided
-                    if idents.len() < 2 {
-                        errors.push(ParserError {
-                            message: "Expected Idnt".to_string(),
-                            start: next_token_data.start,
-                            end: next_token_data.end,
-                        });
-                    }
+                PValue::Property(Property {
+                    _type: "Property".to_string(),
+                    value: idents,
+                    start: propery_init_token.start,
+                    end,
+                })
+            }
+            // {} or {{ - leave the token to whoever closes or opens the tag
+            TToken::CloseTag | TToken::OpenTag | TToken::Text(_) => {
+                let start = self.previous_token().end;
+                errors.push(ParserError {
+                    message: "Expected Identifyer".to_string(),
+                    start,
+                    end: start,
+                });
+                PValue::Missing {
+                    _type: "Missing".to_string(),
+                    start,
+                    end: start,
                 }
-                // "TToken::ArgumentInitalizer"{Idnt|...} - should return the ident collected
-                TToken::ArgumentInitalizer | TToken::CloseTag | TToken::Text(_) | TToken::WS => {}
-                TToken::ArgumentSeperator
-                | TToken::String(_)
-                | TToken::Ident(_)
-                | TToken::Int(_)
-                | TToken::OpenTag => {
-                    self.advance();
-                    let end_token =
-                        self.advance_until(vec![TToken::ArgumentInitalizer, TToken::CloseTag]);
-
-                    let end_position = {
-                        if end_token.is_some() {
-                            end_token.unwrap().end
-                        } else {
-                            next_token_data.end
+            }
+            // {"name"} or {0 | ...}
+            _ => {
+                self.advance();
+                self.advance_until(&[TToken::ArgumentInitalizer, TToken::CloseTag]);
+                errors.push(ParserError {
+                    message: "Expected Identifyer".to_string(),
+                    start: propery_init_token.start,
+                    end: propery_init_token.start,
+                });
+                PValue::Error(self.error_node(&propery_init_token))
+            }
+        }
+    }
+
+    /// Everything after the first ident of a property. Aka: ".guild.name"
+    fn tag_property_rest(
+        &mut self,
+        next_token_data: Token<'src>,
+        idents: &mut Vec<String>,
+        errors: &mut Vec<ParserError>,
+        skipped: &mut Vec<ErrorNode>,
+    ) {
+        match next_token_data.token {
+            TToken::Dot => {
+                self.advance();
+
+                let mut last_was_dot = true;
+                while let Some(token_safe) = self.peek().copied() {
+                    match token_safe.token {
+                        TToken::Dot => {
+                            self.advance();
+                            if last_was_dot {
+                                errors.push(ParserError {
+                                    message: "Unexpected '.'".to_string(),
+                                    start: token_safe.start,
+                                    end: token_safe.end,
+                                });
+                                skipped.push(self.error_node(&token_safe));
+                            } else {
+                                last_was_dot = true;
+                            }
                         }
+                        TToken::ArgumentSeperator | TToken::Int(_) | TToken::Raw => {
+                            self.advance();
+                            errors.push(ParserError {
+                                message: "Unexpected Token".to_string(),
+                                start: token_safe.start,
+                                end: token_safe.end,
+                            });
+                            skipped.push(self.error_node(&token_safe));
+                        }
+                        TToken::Ident(idnt) => {
+                            self.advance();
+                            if !last_was_dot {
+                                // // Unsure if we should have the same recovery behaver as with the first instance of just skiping...
+                                let end_token = self
+                                    .advance_until(&[TToken::ArgumentInitalizer, TToken::CloseTag]);
+
+                                let end_position = match end_token {
+                                    Some(end_token) => end_token.end,
+                                    None => token_safe.end,
+                                };
+
+                                errors.push(ParserError {
+                                    message: "Unexpected Token".to_string(),
+                                    start: token_safe.start,
+                                    end: end_position,
+                                });
+                                skipped.push(self.error_node(&token_safe));
+                            } else {
+                                idents.push(idnt.to_string());
+                                last_was_dot = false;
+                            }
+                        }
+                        // {idnt.something ""} -> not allowed
+                        TToken::String(_) => {
+                            self.advance();
+                            errors.push(ParserError {
+                                message: "Unexpected String".to_string(),
+                                start: token_safe.start,
+                                end: token_safe.end,
+                            });
+                            skipped.push(self.error_node(&token_safe));
+                        }
+                        // Never part of the property, 'tag_expression' reports the unclosed tag
+                        TToken::WS | TToken::OpenTag | TToken::Text(_) => break,
+                        TToken::ArgumentInitalizer | TToken::CloseTag => break, //  _ => break,
                     };
+                }
 
+                // Aka: {Idnt.} - no follow up was provided
+                if idents.len() < 2 {
                     errors.push(ParserError {
-                        message: "Unexpected Token".to_string(),
+                        message: "Expected Idnt".to_string(),
                         start: next_token_data.start,
-                        end: end_position,
+                        end: next_token_data.end,
                     });
                 }
             }
+            // "TToken::ArgumentInitalizer"{Idnt|...} - should return the ident collected
+            TToken::ArgumentInitalizer
+            | TToken::CloseTag
+            | TToken::Text(_)
+            | TToken::WS
+            | TToken::OpenTag => {}
+            TToken::ArgumentSeperator
+            | TToken::String(_)
+            | TToken::Ident(_)
+            | TToken::Int(_)
+            | TToken::Raw => {
+                self.advance();
+                let end_token = self.advance_until(&[TToken::ArgumentInitalizer, TToken::CloseTag]);
 
-            let property_last_token = self.tokens[self.pointer - 1].clone();
-
-            // When the last token is a closing it should be the token's 'start-1' otherwise it should be 'end'
-            let end = {
-                match property_last_token.token {
-                    TToken::CloseTag => {
-                        let st = property_last_token.start;
-                        PPosition {
-                            column: st.column,
-                            line: st.line - 1,
-                        }
-                    }
-                    _ => property_last_token.end,
-                }
-            };
+                let end_position = match end_token {
+                    Some(end_token) => end_token.end,
+                    None => next_token_data.end,
+                };
 
-            Some(PValue::Property(Property {
-                _type: "Property".to_string(),
-                value: idents,
-                start: propery_init_token.start,
-                end,
-            }))
-        } else {
-            errors.push(ParserError {
-                message: "Expected Identifyer".to_string(),
-                start: propery_init_token.start.clone(),
-                end: propery_init_token.start,
-            });
-            None
+                errors.push(ParserError {
+                    message: "Unexpected Token".to_string(),
+                    start: next_token_data.start,
+                    end: end_position,
+                });
+                skipped.push(self.error_node(&next_token_data));
+            }
         }
     }
 
-    fn tag_arguments(&mut self, mut errors: &mut Vec<ParserError>) -> Option<Vec<Arg>> {
-        let peek_res = self.peek();
-        if peek_res.is_none() {
-            let last_token = self.tokens[self.tokens.len() - 1].clone();
-            errors.push(ParserError {
-                message: "Unexpected EOF".to_string(),
-                start: last_token.start,
-                end: last_token.end,
-            });
-            return None;
-        }
-        let argument_init_token = peek_res.unwrap();
+    fn tag_arguments(&mut self, errors: &mut Vec<ParserError>) -> Option<Vec<Arg>> {
+        // The unclosed tag is reported by 'tag_expression'
+        let argument_init_token = self.peek().copied()?;
 
         if argument_init_token.token == TToken::ArgumentInitalizer {
             self.advance();
@@ -407,9 +507,9 @@ impl Parser {
 
             let mut expect_seperator = false;
             while !self.is_at_end() {
-                let next_token_data = self.peek();
+                let next_token_data = self.peek().copied();
                 if next_token_data.is_none() {
-                    let last_token = self.tokens[self.tokens.len() - 1].clone();
+                    let last_token = self.previous_token();
                     errors.push(ParserError {
                         message: "Unexpected EOF expected Arg".to_string(),
                         start: last_token.start,
@@ -425,20 +525,20 @@ impl Parser {
                         if expect_seperator {
                             errors.push(ParserError {
                                 message: "Expected ';'".to_string(),
-                                start: next_token.start.clone(),
-                                end: next_token.start.clone(),
+                                start: next_token.start,
+                                end: next_token.start,
                             })
                         }
                         arguments.push(Arg::Single(Argument {
                             _type: "ArgSingle".to_string(),
                             value: PValue::String {
                                 _type: "String".to_string(),
-                                start: next_token.start.clone(),
-                                end: next_token.end.clone(),
-                                value: text,
+                                start: next_token.start,
+                                end: next_token.end,
+                                value: text.to_string(),
                             },
-                            start: next_token.start.clone(),
-                            end: next_token.end.clone(),
+                            start: next_token.start,
+                            end: next_token.end,
                         }));
                         expect_seperator = true;
                     }
@@ -447,16 +547,16 @@ impl Parser {
                         if expect_seperator {
                             errors.push(ParserError {
                                 message: "Expected ';'".to_string(),
-                                start: next_token.start.clone(),
-                                end: next_token.start.clone(),
+                                start: next_token.start,
+                                end: next_token.start,
                             })
                         }
                         arguments.push(Arg::Single(Argument {
                             _type: "ArgSingle".to_string(),
                             value: PValue::Int {
                                 _type: "Int".to_string(),
-                                start: next_token.start.clone(),
-                                end: next_token.end.clone(),
+                                start: next_token.start,
+                                end: next_token.end,
                                 value: int,
                             },
                             start: next_token.start,
@@ -469,47 +569,65 @@ impl Parser {
                         if expect_seperator {
                             errors.push(ParserError {
                                 message: "Expected ';'".to_string(),
-                                start: next_token.start.clone(),
-                                end: next_token.start.clone(),
+                                start: next_token.start,
+                                end: next_token.start,
                             })
                         }
+                        let mut skipped = Vec::new();
                         let idents_property = self.tag_arg_construct_ident(
-                            next_token.start.clone(),
-                            next_token.end.clone(),
+                            next_token.start,
                             ident,
-                            &mut errors,
+                            errors,
+                            &mut skipped,
                         );
 
                         arguments.push(Arg::Single(Argument {
                             _type: "ArgSingle".to_string(),
-                            start: idents_property.start.clone(),
-                            end: idents_property.end.clone(),
+                            start: idents_property.start,
+                            end: idents_property.end,
                             value: PValue::Property(idents_property),
                         }));
+                        arguments.extend(skipped.into_iter().map(Arg::Error));
                         expect_seperator = true;
                     }
                     TToken::ArgumentSeperator => {
                         self.advance();
+                        // {t | ; a} or {t | a ; ; b}
+                        if !expect_seperator {
+                            errors.push(ParserError {
+                                message: "Expected Arg before ';'".to_string(),
+                                start: next_token.start,
+                                end: next_token.end,
+                            });
+                            arguments.push(Arg::Error(self.error_node(&next_token)));
+                        }
                         expect_seperator = false;
                     }
-                    TToken::ArgumentInitalizer | TToken::Dot => {
+                    TToken::ArgumentInitalizer | TToken::Dot | TToken::Raw => {
                         self.advance();
                         errors.push(ParserError {
                             message: "Unexpected Token".to_string(),
                             start: next_token.start,
                             end: next_token.end,
                         });
+                        arguments.push(Arg::Error(self.error_node(&next_token)));
                     }
-                    TToken::Text(_) | TToken::WS | TToken::OpenTag => {}
+                    TToken::Text(_) | TToken::WS | TToken::OpenTag => break,
                     TToken::CloseTag => break,
                 }
             }
 
-            if arguments.len() > 0 {
-                Some(arguments)
-            } else {
-                None
+            // {t |}
+            if arguments.is_empty() {
+                errors.push(ParserError {
+                    message: "Expected Arg".to_string(),
+                    start: argument_init_token.start,
+                    end: argument_init_token.end,
+                });
+                return None;
             }
+
+            Some(arguments)
         } else {
             None
         }
@@ -521,138 +639,183 @@ impl Parser {
     fn tag_arg_construct_ident(
         &mut self,
         token_start: PPosition,
-        token_end: PPosition,
-        inital: String,
+        inital: &str,
         errors: &mut Vec<ParserError>,
+        skipped: &mut Vec<ErrorNode>,
     ) -> Property {
         let mut idents: Vec<String> = Vec::new();
-        idents.push(inital);
+        idents.push(inital.to_string());
+        let mut end = self.previous_token().end;
 
         let mut last_was_dot = false;
-        while !self.is_at_end() {
-            let token_data = self.peek();
-            if let Some(token_safe) = token_data {
-                match token_safe.token {
-                    TToken::Dot => {
-                        if last_was_dot {
-                            errors.push(ParserError {
-                                message: "Unexpected '.'".to_string(),
-                                start: token_safe.start,
-                                end: token_safe.end,
-                            });
-                        } else {
-                            last_was_dot = true
-                        }
-                        self.advance();
+        while let Some(token_safe) = self.peek().copied() {
+            match token_safe.token {
+                TToken::Dot => {
+                    self.advance();
+                    if last_was_dot {
+                        errors.push(ParserError {
+                            message: "Unexpected '.'".to_string(),
+                            start: token_safe.start,
+                            end: token_safe.end,
+                        });
+                        skipped.push(self.error_node(&token_safe));
+                    } else {
+                        last_was_dot = true
                     }
-                    TToken::Ident(ident) => {
-                        self.advance();
-                        if !last_was_dot {
-                            let end_token = self
-                                .advance_until(vec![TToken::ArgumentSeperator, TToken::CloseTag]);
-
-                            let end_position = {
-                                if end_token.is_some() {
-                                    end_token.unwrap().end
-                                } else {
-                                    token_safe.end
-                                }
-                            };
+                }
+                TToken::Ident(ident) => {
+                    self.advance();
+                    if !last_was_dot {
+                        let end_token =
+                            self.advance_until(&[TToken::ArgumentSeperator, TToken::CloseTag]);
+
+                        let end_position = match end_token {
+                            Some(end_token) => end_token.end,
+                            None => token_safe.end,
+                        };
 
-                            errors.push(ParserError {
-                                message: "Unexpected Token".to_string(),
-                                start: token_safe.start,
-                                end: end_position,
-                            });
-                        } else {
-                            idents.push(ident);
-                            last_was_dot = false;
-                        }
-                    }
-                    TToken::String(_) => {
-                        self.advance();
                         errors.push(ParserError {
                             message: "Unexpected Token".to_string(),
                             start: token_safe.start,
-                            end: token_safe.end,
-                        })
-                    }
-                    TToken::ArgumentInitalizer | TToken::Int(_) => {
-                        self.advance();
+                            end: end_position,
+                        });
+                        skipped.push(self.error_node(&token_safe));
+                    } else {
+                        idents.push(ident.to_string());
+                        last_was_dot = false;
+                        end = token_safe.end;
                     }
-                    TToken::Text(_) | TToken::WS | TToken::OpenTag => {}
-                    TToken::CloseTag => break,
-                    TToken::ArgumentSeperator => break,
-                };
-            } else {
-                break;
-            }
+                }
+                TToken::String(_) | TToken::ArgumentInitalizer | TToken::Int(_) | TToken::Raw => {
+                    self.advance();
+                    errors.push(ParserError {
+                        message: "Unexpected Token".to_string(),
+                        start: token_safe.start,
+                        end: token_safe.end,
+                    });
+                    skipped.push(self.error_node(&token_safe));
+                }
+                TToken::Text(_) | TToken::WS | TToken::OpenTag => break,
+                TToken::CloseTag => break,
+                TToken::ArgumentSeperator => break,
+            };
+        }
+
+        // Aka: {t | guild.} - no follow up was provided
+        if last_was_dot {
+            let last_token = self.previous_token();
+            errors.push(ParserError {
+                message: "Expected Idnt".to_string(),
+                start: last_token.start,
+                end: last_token.end,
+            });
         }
 
         Property {
             _type: "Property".to_string(),
             value: idents,
             start: token_start,
-            end: token_end,
+            end,
+        }
+    }
+
+    /// Node for the tokens consumed from `first` up to the last advanced token.
+    /// Keeps what the parser skipped while recovering in the tree.
+    fn error_node(&self, first: &Token<'src>) -> ErrorNode {
+        let last = self.previous_token();
+        ErrorNode {
+            _type: "Error".to_string(),
+            start: first.start,
+            end: last.end,
+            span: Span {
+                start: first.span.start,
+                end: last.span.end,
+            },
         }
     }
 
     // returns the final token
-    fn advance_until(&mut self, skip_until: Vec<TToken>) -> Option<Token> {
-        if skip_until.len() > 0 {
-            while !self.is_at_end() {
-                let peeked = self.peek();
-                // none only if it's the end of the program, so should not happen
-                if peeked.is_none() {
-                    break;
-                }
+    fn advance_until(&mut self, skip_until: &[TToken]) -> Option<Token<'src>> {
+        if skip_until.is_empty() {
+            return None;
+        }
 
-                if skip_until.contains(&peeked.as_ref().unwrap().token) {
-                    return Some(peeked.unwrap());
-                }
-                self.advance();
+        // none only if it's the end of the program
+        while let Some(peeked) = self.peek() {
+            if skip_until.contains(&peeked.token) {
+                return Some(*peeked);
             }
-
-            None
-        } else {
-            None
+            // Text and '{' are never part of a tag, the tag was not closed
+            if matches!(peeked.token, TToken::Text(_) | TToken::OpenTag) {
+                break;
+            }
+            self.advance();
         }
+
+        None
     }
 
-    fn advance(&mut self) -> Option<Token> {
-        let token = self.peek();
-        self.pointer += 1;
+    fn advance(&mut self) -> Option<Token<'src>> {
+        self.visit_ws();
+        let token = self.lookahead.pop_front();
+        if token.is_some() {
+            self.previous = token;
+        }
         token
     }
 
-    fn peek(&mut self) -> Option<Token> {
-        if self.is_at_end() {
-            None
-        } else {
-            self.visit_ws();
-            if self.is_at_end() {
-                return None;
-            }
-            Some(self.tokens[self.pointer].clone())
-        }
+    fn peek(&mut self) -> Option<&Token<'src>> {
+        self.visit_ws();
+        self.lookahead.front()
+    }
+
+    /// The last consumed token, falls back to an empty token at the start of the source
+    fn previous_token(&self) -> Token<'src> {
+        self.previous.unwrap_or(Token {
+            token: TToken::WS,
+            start: PPosition { column: 0, line: 0 },
+            end: PPosition { column: 0, line: 0 },
+            span: Span { start: 0, end: 0 },
+        })
     }
 
     /// Used in side peek to ignore whitespace
     /// whitespace is not used in the parser. It is only used in the formater
     fn visit_ws(&mut self) {
-        while !self.is_at_end() {
-            let current_token = self.tokens[self.pointer].clone();
-            match current_token.token {
-                TToken::WS => {
-                    self.pointer += 1;
+        while self.fill_lookahead() {
+            match self.lookahead.front() {
+                Some(Token {
+                    token: TToken::WS, ..
+                }) => {
+                    self.lookahead.pop_front();
                 }
                 _ => break,
-            };
+            }
+        }
+    }
+
+    /// Makes sure there is a token in the lookahead, pulling one from the lexer if needed.
+    /// Returns false once the source is exhausted.
+    fn fill_lookahead(&mut self) -> bool {
+        if !self.lookahead.is_empty() {
+            return true;
         }
+
+        for token in self.lexer.by_ref() {
+            match token {
+                Ok(token) => {
+                    self.lookahead.push_back(token);
+                    return true;
+                }
+                Err(err) => self.lex_errors.push(err.into()),
+            }
+        }
+
+        false
     }
 
-    fn is_at_end(&self) -> bool {
-        self.pointer >= self.tokens.len()
+    fn is_at_end(&mut self) -> bool {
+        self.peek().is_none()
     }
 }
 
@@ -664,9 +827,8 @@ mod tests {
     fn parse_base(program: &str) -> Result<ParserResults, String> {
         let mut lex = Lexer::from_source(program);
         let res = lex.scan_tokens();
-        if res.is_err() {
-            println!("{:?}", res);
-            return Err(res.err().unwrap());
+        if let Err(err) = res {
+            return Err(err.message);
         }
 
         let mut parser = Parser::from_lexer(lex);
@@ -675,17 +837,26 @@ mod tests {
 
     #[test]
     fn tag_property_only() {
-        assert!(parse_base("h{ guild }").unwrap().errors.len() == 0);
-        assert!(parse_base("h{ guild . name }").unwrap().errors.len() == 0);
+        assert!(parse_base("h{ guild }").unwrap().errors.is_empty());
+        assert!(parse_base("h{ guild . name }").unwrap().errors.is_empty());
+        assert!(parse_base("h{ user2.données }").unwrap().errors.is_empty());
     }
 
     #[test]
     fn tag_property_errs() {
-        assert!(parse_base("h{guild.}").unwrap().errors.len() > 0);
-        assert!(parse_base("h{guild..}").unwrap().errors.len() > 0);
-        assert!(parse_base("h{guild").unwrap().errors.len() > 0);
-
-        assert!(parse_base("h{ {guild").unwrap().errors.len() > 0);
+        assert!(!parse_base("h{guild.}").unwrap().errors.is_empty());
+        assert!(!parse_base("h{guild..}").unwrap().errors.is_empty());
+        assert!(!parse_base("h{guild").unwrap().errors.is_empty());
+
+        assert!(!parse_base("h{ {guild").unwrap().errors.is_empty());
+        assert!(!parse_base("h{guild}} name").unwrap().errors.is_empty());
+        assert!(!parse_base("h{guild.\n}").unwrap().errors.is_empty());
+        assert!(!parse_base("h{t | guild.}").unwrap().errors.is_empty());
+        assert!(!parse_base("h{a |}").unwrap().errors.is_empty());
+        assert!(!parse_base("h{a | ; }").unwrap().errors.is_empty());
+        assert!(!parse_base("h{a | b ; ; c}").unwrap().errors.is_empty());
+        // A trailing ';' is allowed by the grammar
+        assert!(parse_base("h{a | b ;}").unwrap().errors.is_empty());
         // NOTE: Not parsed as would like but good enough.
         // after the tag parse of 'tag_property' ends and ['expected identifyer', 'expected "}"'] are errors returned
         // *this is not a bug, but a feature :>jk
@@ -700,4 +871,136 @@ mod tests {
         // println!("{:#?}", parse_base("{t|guild}").unwrap());
         println!("{:#?}", parse_base("test \n {g | t |}").unwrap());
     }
+
+    #[test]
+    fn streamed_lex_errors() {
+        let results = Parser::from_lexer(Lexer::from_source("a {guild # | \"b}")).parse();
+        let messages: Vec<_> = results.errors.iter().map(|e| e.message.as_str()).collect();
+        assert!(messages.contains(&"Unexpected token"));
+        assert!(messages.contains(&"Unterminated string"));
+        assert_eq!(results.ast.body.len(), 2);
+    }
+
+    fn first_tag(program: &str) -> Expression {
+        let results = parse_base(program).unwrap();
+        results
+            .ast
+            .body
+            .into_iter()
+            .find_map(|stat| match stat {
+                Stat::Tag { value, .. } => Some(value),
+                _ => None,
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn raw_marker() {
+        assert!(first_tag("{!user.bio}").raw);
+        assert!(first_tag("{ ! user.bio | 1}").raw);
+        assert!(!first_tag("{user.bio}").raw);
+
+        assert!(!parse_base("{user.bio!}").unwrap().errors.is_empty());
+        assert!(!parse_base("{!!user.bio}").unwrap().errors.is_empty());
+        assert!(!parse_base("{t | !user.bio}").unwrap().errors.is_empty());
+    }
+
+    #[test]
+    fn recovery_nodes() {
+        let PValue::Property(property) = first_tag("h{guild").property else {
+            panic!("unclosed tag should keep its property")
+        };
+        assert_eq!(property.value, vec!["guild"]);
+
+        assert!(matches!(first_tag("h{}").property, PValue::Missing { .. }));
+        assert!(matches!(first_tag("h{").property, PValue::Missing { .. }));
+
+        let PValue::Error(node) = first_tag("h{\"guild\" 5 | a}").property else {
+            panic!("a string is not a property")
+        };
+        assert_eq!((node.span.start, node.span.end), (2, 11));
+
+        let tag = first_tag("h{guild.name foo bar | a}");
+        assert_eq!(tag.skipped.len(), 1);
+        assert_eq!(
+            (tag.skipped[0].span.start, tag.skipped[0].span.end),
+            (13, 20)
+        );
+        assert_eq!(tag.arguments.unwrap().len(), 1);
+
+        let arguments = first_tag("h{t | a | ; 5}").arguments.unwrap();
+        assert!(matches!(arguments[1], Arg::Error(_)));
+        assert_eq!(arguments.len(), 3);
+
+        let results = parse_base("h{a}} b").unwrap();
+        let Some(Stat::Tag { span, .. }) = results.ast.body.get(1) else {
+            panic!("expected the tag")
+        };
+        assert_eq!((span.start, span.end), (1, 4));
+        let Some(Stat::Error(node)) = results.ast.body.get(2) else {
+            panic!("the stray '}}' should be kept in the body")
+        };
+        assert_eq!((node.span.start, node.span.end), (4, 5));
+        assert_eq!(results.errors.len(), 1);
+
+        // One problem, one error
+        assert_eq!(parse_base("h{a\n").unwrap().errors.len(), 1);
+    }
+
+    /// xorshift64, keeps the property test deterministic without pulling in a crate
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+    }
+
+    #[test]
+    fn never_panics_on_arbitrary_input() {
+        // Pieces of the syntax, so most cases get past the lexer and exercise the parser
+        const PIECES: &[&str] = &[
+            "{",
+            "}",
+            "|",
+            ";",
+            ".",
+            "\"",
+            " ",
+            "\n",
+            "\r",
+            "\t",
+            "guild",
+            "a",
+            "é",
+            "0",
+            "42",
+            "99999999999",
+            "#",
+            "!",
+            "$",
+            "_",
+        ];
+
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+        for case in 0..20_000 {
+            let len = (rng.next() % 24) as usize;
+            let source = if case % 4 == 0 {
+                let bytes: Vec<u8> = (0..len).map(|_| rng.next() as u8).collect();
+                String::from_utf8_lossy(&bytes).into_owned()
+            } else {
+                (0..len)
+                    .map(|_| PIECES[rng.next() as usize % PIECES.len()])
+                    .collect()
+            };
+
+            let res = std::panic::catch_unwind(|| {
+                Parser::from_lexer(Lexer::from_source(&source)).parse()
+            });
+            assert!(res.is_ok(), "parser panicked on {:?}", source);
+        }
+    }
 }
@@ -1,11 +1,21 @@
+use unicode_ident::{is_xid_continue, is_xid_start};
+
 pub fn is_digit(char: char) -> bool {
-    char >= '0' && char <= '9'
+    char.is_ascii_digit()
 }
 
+/// Can `char` start an identifier (Unicode XID_Start, `_` or `$`)
 pub fn is_alpha(char: char) -> bool {
-    (char >= 'a' && char <= 'z') || (char >= 'A' && char <= 'Z') || char == '_' || char == '$'
+    is_xid_start(char) || char == '_' || char == '$'
 }
 
+/// Can `char` continue an identifier (Unicode XID_Continue, which includes digits and `_`, or `$`)
 pub fn is_alpha_numeric(char: char) -> bool {
-    is_alpha(char) || is_digit(char)
+    is_alpha(char) || is_xid_continue(char)
+}
+
+/// How the type of a function is written by the runtime and the language server,
+/// aka: "(String, Int?) -> String"
+pub fn signature(arguments: &[String], return_type: &str) -> String {
+    format!("({}) -> {}", arguments.join(", "), return_type)
 }
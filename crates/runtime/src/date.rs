@@ -0,0 +1,298 @@
+use std::{
+    fmt,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// A point in time, in whole seconds since the Unix epoch (UTC).
+///
+/// Seconds are what Discord timestamps (`<t:1700000000>`) and most APIs hand out,
+/// renders as RFC 3339: `2023-11-14T22:13:20Z`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Timestamp(i64);
+
+impl Timestamp {
+    pub fn from_unix(seconds: i64) -> Self {
+        Timestamp(seconds)
+    }
+
+    pub fn unix(&self) -> i64 {
+        self.0
+    }
+
+    pub fn now() -> Self {
+        SystemTime::now().into()
+    }
+
+    pub(crate) fn date_time(&self) -> DateTime {
+        let days = self.0.div_euclid(86_400);
+        let seconds = self.0.rem_euclid(86_400) as u32;
+        let (year, month, day) = civil_from_days(days);
+
+        DateTime {
+            year,
+            month,
+            day,
+            hour: seconds / 3600,
+            minute: seconds % 3600 / 60,
+            second: seconds % 60,
+            // 1970-01-01 was a Thursday
+            weekday: (days + 4).rem_euclid(7) as u32,
+            day_of_year: (days - days_from_civil(year, 1, 1)) as u32 + 1,
+        }
+    }
+
+    /// Formats with strftime-style specifiers, in UTC
+    ///
+    /// `%Y` year, `%y` 2 digit year, `%m` month, `%B` month name, `%b` short month name,
+    /// `%d` day, `%e` day without padding, `%j` day of the year, `%A` weekday name, `%a` short weekday name,
+    /// `%H` hour, `%I` 12 hour clock hour, `%p` AM/PM, `%M` minute, `%S` second, `%s` unix seconds,
+    /// `%Z` timezone (always UTC), `%F` for `%Y-%m-%d`, `%T` for `%H:%M:%S` and `%%` for '%'.
+    /// Unknown specifiers are kept as they are.
+    pub fn format(&self, pattern: &str) -> String {
+        use std::fmt::Write;
+
+        let date_time = self.date_time();
+        let mut formatted = String::new();
+        let mut chars = pattern.chars();
+
+        while let Some(char) = chars.next() {
+            if char != '%' {
+                formatted.push(char);
+                continue;
+            }
+            // Writing to a String can't fail
+            let _ = match chars.next() {
+                Some('Y') => write!(formatted, "{:04}", date_time.year),
+                Some('y') => write!(formatted, "{:02}", date_time.year.rem_euclid(100)),
+                Some('m') => write!(formatted, "{:02}", date_time.month),
+                Some('B') => write!(formatted, "{}", MONTHS[date_time.month as usize - 1]),
+                Some('b') => write!(formatted, "{}", &MONTHS[date_time.month as usize - 1][..3]),
+                Some('d') => write!(formatted, "{:02}", date_time.day),
+                Some('e') => write!(formatted, "{}", date_time.day),
+                Some('j') => write!(formatted, "{:03}", date_time.day_of_year),
+                Some('A') => write!(formatted, "{}", WEEKDAYS[date_time.weekday as usize]),
+                Some('a') => write!(formatted, "{}", &WEEKDAYS[date_time.weekday as usize][..3]),
+                Some('H') => write!(formatted, "{:02}", date_time.hour),
+                Some('I') => write!(formatted, "{:02}", (date_time.hour + 11) % 12 + 1),
+                Some('p') => write!(
+                    formatted,
+                    "{}",
+                    if date_time.hour < 12 { "AM" } else { "PM" }
+                ),
+                Some('M') => write!(formatted, "{:02}", date_time.minute),
+                Some('S') => write!(formatted, "{:02}", date_time.second),
+                Some('s') => write!(formatted, "{}", self.0),
+                Some('Z') => write!(formatted, "UTC"),
+                Some('F') => write!(formatted, "{}", self.format("%Y-%m-%d")),
+                Some('T') => write!(formatted, "{}", self.format("%H:%M:%S")),
+                Some('%') => write!(formatted, "%"),
+                Some(other) => write!(formatted, "%{}", other),
+                None => write!(formatted, "%"),
+            };
+        }
+
+        formatted
+    }
+
+    /// How long ago (or in how long) this is from `now`. Aka: "3 days ago", "in 2 hours", "just now"
+    pub fn relative_to(&self, now: Timestamp) -> String {
+        // Wider than the timestamps, so "i64::MIN from now" doesn't overflow
+        let difference = i128::from(self.0) - i128::from(now.0);
+        let seconds = difference.unsigned_abs();
+
+        let (count, unit) = match seconds {
+            0..=44 => return "just now".to_string(),
+            45..=3_599 => ((seconds + 30) / 60, "minute"),
+            3_600..=86_399 => ((seconds + 1_800) / 3_600, "hour"),
+            86_400..=2_591_999 => ((seconds + 43_200) / 86_400, "day"),
+            2_592_000..=31_535_999 => ((seconds + 1_296_000) / 2_592_000, "month"),
+            _ => ((seconds + 15_768_000) / 31_536_000, "year"),
+        };
+        let plural = if count == 1 { "" } else { "s" };
+
+        if difference < 0 {
+            format!("{} {}{} ago", count, unit, plural)
+        } else {
+            format!("in {} {}{}", count, unit, plural)
+        }
+    }
+}
+
+/// Where the current time comes from, swapped for a `FixedClock` to get reproducible renders
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Timestamp;
+}
+
+/// The time of the system
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Timestamp {
+        Timestamp::now()
+    }
+}
+
+/// Always the same time, for tests and previews
+#[derive(Debug, Clone, Copy)]
+pub struct FixedClock(pub Timestamp);
+
+impl Clock for FixedClock {
+    fn now(&self) -> Timestamp {
+        self.0
+    }
+}
+
+/// Calendar fields of a timestamp, in UTC
+pub(crate) struct DateTime {
+    pub(crate) year: i64,
+    pub(crate) month: u32,
+    pub(crate) day: u32,
+    pub(crate) hour: u32,
+    pub(crate) minute: u32,
+    pub(crate) second: u32,
+    /// 0 is Sunday
+    pub(crate) weekday: u32,
+    /// 1 is the first of January
+    pub(crate) day_of_year: u32,
+}
+
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+const WEEKDAYS: [&str; 7] = [
+    "Sunday",
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+];
+
+impl From<SystemTime> for Timestamp {
+    fn from(time: SystemTime) -> Self {
+        match time.duration_since(UNIX_EPOCH) {
+            Ok(after) => Timestamp(after.as_secs() as i64),
+            // Before 1970, round down to the previous whole second
+            Err(before) => {
+                let before = before.duration();
+                let seconds = before.as_secs() as i64 + i64::from(before.subsec_nanos() > 0);
+                Timestamp(-seconds)
+            }
+        }
+    }
+}
+
+impl From<Timestamp> for SystemTime {
+    fn from(timestamp: Timestamp) -> Self {
+        let seconds = Duration::from_secs(timestamp.0.unsigned_abs());
+        if timestamp.0 >= 0 {
+            UNIX_EPOCH + seconds
+        } else {
+            UNIX_EPOCH - seconds
+        }
+    }
+}
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.format("%Y-%m-%dT%H:%M:%SZ"))
+    }
+}
+
+/// (year, month, day) of the proleptic Gregorian calendar from days since 1970-01-01.
+/// <https://howardhinnant.github.io/date_algorithms.html#civil_from_days>
+pub(crate) fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_pre = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_pre + 2) / 5 + 1) as u32;
+    let month = if month_pre < 10 {
+        month_pre + 3
+    } else {
+        month_pre - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    (year, month, day)
+}
+
+/// Days since 1970-01-01 from a (year, month, day) of the proleptic Gregorian calendar.
+/// <https://howardhinnant.github.io/date_algorithms.html#days_from_civil>
+pub(crate) fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month = i64::from(month);
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146_097 + day_of_era - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_rfc3339() {
+        assert_eq!(Timestamp::from_unix(0).to_string(), "1970-01-01T00:00:00Z");
+        assert_eq!(
+            Timestamp::from_unix(1_700_000_000).to_string(),
+            "2023-11-14T22:13:20Z"
+        );
+        // Leap day
+        assert_eq!(
+            Timestamp::from_unix(951_782_400).to_string(),
+            "2000-02-29T00:00:00Z"
+        );
+        assert_eq!(Timestamp::from_unix(-1).to_string(), "1969-12-31T23:59:59Z");
+
+        let time = SystemTime::from(Timestamp::from_unix(-90));
+        assert_eq!(Timestamp::from(time).unix(), -90);
+    }
+
+    #[test]
+    fn format_and_relative() {
+        // Tuesday 14th of November 2023, 22:13:20
+        let timestamp = Timestamp::from_unix(1_700_000_000);
+        assert_eq!(
+            timestamp.format("%A %e %B %Y, %I:%M %p (%a %b %y %j) %F %T %Z %s 100%% %q"),
+            "Tuesday 14 November 2023, 10:13 PM (Tue Nov 23 318) 2023-11-14 22:13:20 UTC 1700000000 100% %q"
+        );
+        assert_eq!(days_from_civil(2023, 11, 14), 1_700_000_000 / 86_400);
+
+        let relative =
+            |seconds: i64| Timestamp::from_unix(1_700_000_000 + seconds).relative_to(timestamp);
+        assert_eq!(relative(-10), "just now");
+        assert_eq!(relative(-60), "1 minute ago");
+        assert_eq!(relative(-3 * 86_400), "3 days ago");
+        assert_eq!(relative(2 * 3_600), "in 2 hours");
+        assert_eq!(relative(-400 * 86_400), "1 year ago");
+
+        let extreme = |seconds: i64| Timestamp::from_unix(seconds).relative_to(timestamp);
+        assert_eq!(extreme(i64::MIN), "292471208731 years ago");
+        assert_eq!(extreme(i64::MAX), "in 292471208624 years");
+        assert!(Timestamp::from_unix(i64::MIN)
+            .relative_to(Timestamp::from_unix(i64::MAX))
+            .ends_with("years ago"));
+    }
+}
@@ -0,0 +1,201 @@
+use jtl_parser::{lex::PPosition, parser::ParserError};
+use std::{fmt, time::Duration};
+
+/// Why a template failed to run.
+///
+/// Every variant carries the `start` and `end` of the offending part of the template,
+/// use `RuntimeError::render` to point at it in the source.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RuntimeError {
+    /// The template could not be lexed or parsed
+    Syntax {
+        message: String,
+        start: PPosition,
+        end: PPosition,
+    },
+    /// A property that is not in the context. Aka: "{guild.nmae}"
+    UndefinedProperty {
+        path: Vec<String>,
+        start: PPosition,
+        end: PPosition,
+    },
+    /// Tried to get a property out of a value that has none. Aka: "{guild.name.first}"
+    NotTraversable {
+        path: Vec<String>,
+        kind: &'static str,
+        start: PPosition,
+        end: PPosition,
+    },
+    /// The value can't be written to the output, objects and functions
+    Unrenderable {
+        kind: &'static str,
+        start: PPosition,
+        end: PPosition,
+    },
+    /// A typed function called with the wrong number or types of arguments
+    Arguments {
+        /// Path of the function, aka: "toPlacement"
+        function: String,
+        message: String,
+        start: PPosition,
+        end: PPosition,
+    },
+    /// A message of the locale that is missing, includes itself or failed to render. Aka: "{t | \"welcome\"}"
+    Message {
+        key: String,
+        message: String,
+        start: PPosition,
+        end: PPosition,
+    },
+    /// An argument the runtime can't evaluate (yet)
+    UnsupportedArgument { start: PPosition, end: PPosition },
+    /// An async function in a template rendered with the sync `render`
+    AsyncFunction { start: PPosition, end: PPosition },
+    /// The output went over `Limits::max_output_bytes`, at this part of the template
+    OutputLimit {
+        limit: usize,
+        start: PPosition,
+        end: PPosition,
+    },
+    /// This call went over `Limits::max_function_calls`
+    CallLimit {
+        limit: usize,
+        start: PPosition,
+        end: PPosition,
+    },
+    /// This message went over `Limits::max_message_depth`, by including another one
+    MessageDepth {
+        limit: usize,
+        start: PPosition,
+        end: PPosition,
+    },
+    /// Writing to the output failed, aka: a closed socket
+    Write {
+        message: String,
+        start: PPosition,
+        end: PPosition,
+    },
+    /// The render went over `Limits::timeout` before getting to this part of the template
+    Timeout {
+        limit: Duration,
+        start: PPosition,
+        end: PPosition,
+    },
+}
+
+impl RuntimeError {
+    pub fn start(&self) -> PPosition {
+        self.position().0
+    }
+
+    pub fn end(&self) -> PPosition {
+        self.position().1
+    }
+
+    fn position(&self) -> (PPosition, PPosition) {
+        match self {
+            RuntimeError::Syntax { start, end, .. }
+            | RuntimeError::UndefinedProperty { start, end, .. }
+            | RuntimeError::NotTraversable { start, end, .. }
+            | RuntimeError::Unrenderable { start, end, .. }
+            | RuntimeError::Arguments { start, end, .. }
+            | RuntimeError::Message { start, end, .. }
+            | RuntimeError::UnsupportedArgument { start, end }
+            | RuntimeError::AsyncFunction { start, end }
+            | RuntimeError::OutputLimit { start, end, .. }
+            | RuntimeError::Write { start, end, .. }
+            | RuntimeError::CallLimit { start, end, .. }
+            | RuntimeError::MessageDepth { start, end, .. }
+            | RuntimeError::Timeout { start, end, .. } => (*start, *end),
+        }
+    }
+
+    /// Renders the error with the line of `source` it points at, underlined with carets
+    ///
+    /// ```text
+    /// error: Undefined property 'guild.nmae'
+    ///  --> 1:6
+    ///   |
+    /// 1 | Hi, {guild.nmae}!
+    ///   |      ^^^^^^^^^^
+    /// ```
+    pub fn render(&self, source: &str) -> String {
+        // NOTE: PPosition's 'column' is the line and 'line' is the character in the line
+        let (start, end) = self.position();
+        let line_number = (start.column + 1).to_string();
+        let gutter = " ".repeat(line_number.len());
+        let line = source.lines().nth(start.column).unwrap_or("");
+
+        let line_len = line.chars().count();
+        let caret_start = start.line.min(line_len);
+        let caret_end = if end.column == start.column {
+            end.line.min(line_len)
+        } else {
+            // Spans over several lines only underline the first one
+            line_len
+        };
+        let carets = "^".repeat(caret_end.saturating_sub(caret_start).max(1));
+
+        format!(
+            "error: {}\n{}--> {}:{}\n{} |\n{} | {}\n{} | {}{}",
+            self,
+            gutter,
+            start.column + 1,
+            start.line + 1,
+            gutter,
+            line_number,
+            line,
+            gutter,
+            " ".repeat(caret_start),
+            carets
+        )
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuntimeError::Syntax { message, .. } => write!(f, "{}", message),
+            RuntimeError::UndefinedProperty { path, .. } => {
+                write!(f, "Undefined property '{}'", path.join("."))
+            }
+            RuntimeError::NotTraversable { path, kind, .. } => {
+                write!(f, "Can't get '{}' out of a {}", path.join("."), kind)
+            }
+            RuntimeError::Unrenderable { kind, .. } => write!(f, "Can't render a {}", kind),
+            RuntimeError::Arguments {
+                function, message, ..
+            } => write!(f, "Wrong arguments for '{}': {}", function, message),
+            RuntimeError::Message { key, message, .. } => {
+                write!(f, "Message '{}': {}", key, message)
+            }
+            RuntimeError::UnsupportedArgument { .. } => write!(f, "Unsupported argument"),
+            RuntimeError::AsyncFunction { .. } => {
+                write!(f, "Async functions can only be called with 'render_async'")
+            }
+            RuntimeError::OutputLimit { limit, .. } => {
+                write!(f, "Output is over the limit of {} bytes", limit)
+            }
+            RuntimeError::CallLimit { limit, .. } => {
+                write!(f, "More than {} function calls", limit)
+            }
+            RuntimeError::MessageDepth { limit, .. } => {
+                write!(f, "Messages nested more than {} deep", limit)
+            }
+            RuntimeError::Write { message, .. } => write!(f, "{}", message),
+            RuntimeError::Timeout { limit, .. } => write!(f, "Render took over {:?}", limit),
+        }
+    }
+}
+
+impl std::error::Error for RuntimeError {}
+
+impl From<&ParserError> for RuntimeError {
+    fn from(err: &ParserError) -> Self {
+        RuntimeError::Syntax {
+            message: err.message.clone(),
+            start: err.start,
+            end: err.end,
+        }
+    }
+}
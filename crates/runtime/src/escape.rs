@@ -0,0 +1,109 @@
+use std::{fmt, sync::Arc};
+
+/// An escaper of your own, aka: for a chat platform other than Discord
+pub type EscapeFn = Arc<dyn Fn(&str) -> String + Send + Sync>;
+
+/// How the values of tags are escaped before being written to the output.
+///
+/// Only values coming from the context are escaped, the text of the template is written as is.
+/// A tag marked with '!' is never escaped, aka: "{!user.bio}".
+#[derive(Clone, Default)]
+pub enum Escape {
+    #[default]
+    None,
+    /// Backslashes Discord markdown, so "**hi**" isn't bold
+    Markdown,
+    /// Breaks up '@', so "@everyone" or "<@123>" don't ping anyone
+    Mentions,
+    /// `Markdown` and `Mentions`, for anything written to a Discord message
+    Discord,
+    Html,
+    Custom(EscapeFn),
+}
+
+impl Escape {
+    pub fn escape(&self, text: &str) -> String {
+        match self {
+            Escape::None => text.to_string(),
+            Escape::Markdown => escape_markdown(text),
+            Escape::Mentions => escape_mentions(text),
+            Escape::Discord => escape_mentions(&escape_markdown(text)),
+            Escape::Html => escape_html(text),
+            Escape::Custom(escape) => escape(text),
+        }
+    }
+}
+
+impl fmt::Debug for Escape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Escape::None => write!(f, "None"),
+            Escape::Markdown => write!(f, "Markdown"),
+            Escape::Mentions => write!(f, "Mentions"),
+            Escape::Discord => write!(f, "Discord"),
+            Escape::Html => write!(f, "Html"),
+            Escape::Custom(_) => write!(f, "Custom"),
+        }
+    }
+}
+
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for char in text.chars() {
+        if matches!(
+            char,
+            '\\' | '*' | '_' | '~' | '`' | '|' | '>' | '#' | '-' | '[' | ']'
+        ) {
+            escaped.push('\\');
+        }
+        escaped.push(char);
+    }
+    escaped
+}
+
+fn escape_mentions(text: &str) -> String {
+    // A zero width space after the '@' keeps the text readable but not a mention
+    text.replace('@', "@\u{200B}")
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for char in text.chars() {
+        match char {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            char => escaped.push(char),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapers() {
+        assert_eq!(Escape::None.escape("**@everyone**"), "**@everyone**");
+        assert_eq!(
+            Escape::Markdown.escape("**bold** `code` ~~x~~ ||s|| > # - [a](b) \\"),
+            "\\*\\*bold\\*\\* \\`code\\` \\~\\~x\\~\\~ \\|\\|s\\|\\| \\> \\# \\- \\[a\\](b) \\\\"
+        );
+        assert_eq!(
+            Escape::Mentions.escape("@everyone <@123>"),
+            "@\u{200B}everyone <@\u{200B}123>"
+        );
+        assert_eq!(Escape::Discord.escape("_@here_"), "\\_@\u{200B}here\\_");
+        assert_eq!(
+            Escape::Html.escape("<b>\"Tom\" & 'Jerry'</b>"),
+            "&lt;b&gt;&quot;Tom&quot; &amp; &#39;Jerry&#39;&lt;/b&gt;"
+        );
+        assert_eq!(
+            Escape::Custom(Arc::new(|text| text.to_uppercase())).escape("hi"),
+            "HI"
+        );
+    }
+}
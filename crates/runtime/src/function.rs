@@ -0,0 +1,394 @@
+//! Functions for templates written as plain Rust functions, their arguments checked and converted.
+//!
+//! ```
+//! # use jtl_runtime::runtime::{Context, Runtime};
+//! fn repeat(text: String, times: Option<u32>) -> String {
+//!     text.repeat(times.unwrap_or(2) as usize)
+//! }
+//!
+//! let mut context = Context::new();
+//! context.add_function("repeat".to_string(), repeat);
+//!
+//! let runtime = Runtime::new(context);
+//! assert_eq!(runtime.execute("{repeat | \"ab\"}").unwrap(), "abab");
+//! assert!(runtime.execute("{repeat | 3}").is_err());
+//! ```
+
+use crate::date::Timestamp;
+use crate::object::ObjectSchema;
+use crate::runtime::{ObjectHashMap, Value};
+use jtl_parser::utils::signature;
+#[cfg(feature = "schema")]
+use jtl_service::SFunction;
+use std::sync::Arc;
+
+/// A function with a known signature, made with `IntoFunction`.
+///
+/// Calls with the wrong number or types of arguments fail the render with `RuntimeError::Arguments`.
+#[derive(Clone)]
+pub struct TypedFunction {
+    /// Type of each argument, optional ones end with '?'. Aka: \["String", "Int?"]
+    pub arguments: Vec<String>,
+    pub return_type: String,
+    /// Errors with what is wrong with the arguments
+    pub(crate) call: Arc<dyn Fn(Vec<Value>) -> Result<Value, String> + Send + Sync>,
+}
+
+impl TypedFunction {
+    pub fn call(&self, args: Vec<Value>) -> Result<Value, String> {
+        (self.call)(args)
+    }
+
+    /// Aka: "(String, Int?) -> String"
+    pub fn signature(&self) -> String {
+        signature(&self.arguments, &self.return_type)
+    }
+
+    /// The function for the schema of the language server
+    #[cfg(feature = "schema")]
+    pub fn schema(&self, description: &str) -> SFunction {
+        SFunction {
+            description: vec![description.to_string()],
+            arguments: self.arguments.clone(),
+            return_type: self.return_type.clone(),
+        }
+    }
+}
+
+/// Name of a type in signatures and schemas, aka: "Int"
+pub trait TypeName {
+    fn type_name() -> String;
+
+    /// Adds the objects this type is made of, for `#[derive(JtlObject)]` structs and their fields
+    fn objects(_objects: &mut Vec<ObjectSchema>) {}
+}
+
+/// A type arguments can be converted to
+pub trait FromValue: TypeName + Sized {
+    fn from_value(value: Value) -> Option<Self>;
+
+    /// What a missing argument is converted to, only optional arguments have something for it
+    fn missing() -> Option<Self> {
+        None
+    }
+}
+
+/// Rust functions taking up to 4 `FromValue` arguments and returning a value, aka: `fn(i64) -> String`
+///
+/// # Panics
+///
+/// When an optional argument comes before a required one, aka: `fn(Option<i64>, String)`,
+/// since a call can only leave out the arguments at the end.
+pub trait IntoFunction<Args> {
+    fn into_function(self) -> TypedFunction;
+}
+
+macro_rules! type_name {
+    ($name:literal: $($type:ty),*) => {
+        $(
+            impl TypeName for $type {
+                fn type_name() -> String {
+                    $name.to_string()
+                }
+            }
+        )*
+    };
+}
+
+type_name!("Int": u8, u16, u32, u64, usize, i8, i16, i32, i64);
+type_name!("Float": f32, f64);
+type_name!("String": String, &str);
+type_name!("Bool": bool);
+type_name!("Date": Timestamp);
+type_name!("Object": ObjectHashMap);
+type_name!("Any": Value);
+
+impl<T: TypeName> TypeName for Vec<T> {
+    fn type_name() -> String {
+        "List".to_string()
+    }
+
+    fn objects(objects: &mut Vec<ObjectSchema>) {
+        T::objects(objects)
+    }
+}
+
+impl<T: TypeName> TypeName for Option<T> {
+    fn type_name() -> String {
+        format!("{}?", T::type_name())
+    }
+
+    fn objects(objects: &mut Vec<ObjectSchema>) {
+        T::objects(objects)
+    }
+}
+
+/// Whole numbers, which may have been made a `Float` by being too big for an `Int`
+fn integer(value: &Value) -> Option<i128> {
+    match value {
+        Value::Int(int) => Some(i128::from(*int)),
+        Value::Float(float) if float.is_finite() && float.fract() == 0.0 => Some(*float as i128),
+        _ => None,
+    }
+}
+
+macro_rules! from_integer {
+    ($($int:ty),*) => {
+        $(
+            impl FromValue for $int {
+                fn from_value(value: Value) -> Option<Self> {
+                    integer(&value)?.try_into().ok()
+                }
+            }
+        )*
+    };
+}
+
+from_integer!(u8, u16, u32, u64, usize, i8, i16, i32, i64);
+
+impl FromValue for f64 {
+    fn from_value(value: Value) -> Option<Self> {
+        match value {
+            Value::Int(int) => Some(int as f64),
+            Value::Float(float) => Some(float),
+            _ => None,
+        }
+    }
+}
+
+impl FromValue for f32 {
+    fn from_value(value: Value) -> Option<Self> {
+        f64::from_value(value).map(|float| float as f32)
+    }
+}
+
+impl FromValue for String {
+    fn from_value(value: Value) -> Option<Self> {
+        match value {
+            Value::String(string) => Some(string),
+            _ => None,
+        }
+    }
+}
+
+impl FromValue for bool {
+    fn from_value(value: Value) -> Option<Self> {
+        match value {
+            Value::Bool(bool) => Some(bool),
+            _ => None,
+        }
+    }
+}
+
+impl FromValue for Timestamp {
+    fn from_value(value: Value) -> Option<Self> {
+        match value {
+            Value::Date(timestamp) => Some(timestamp),
+            _ => None,
+        }
+    }
+}
+
+impl FromValue for ObjectHashMap {
+    fn from_value(value: Value) -> Option<Self> {
+        match value {
+            Value::Object(object) => Some(object),
+            _ => None,
+        }
+    }
+}
+
+impl FromValue for Value {
+    fn from_value(value: Value) -> Option<Self> {
+        Some(value)
+    }
+}
+
+impl<T: FromValue> FromValue for Vec<T> {
+    fn from_value(value: Value) -> Option<Self> {
+        match value {
+            Value::List(items) => items.into_iter().map(T::from_value).collect(),
+            _ => None,
+        }
+    }
+}
+
+/// Optional argument, when missing or null
+impl<T: FromValue> FromValue for Option<T> {
+    fn from_value(value: Value) -> Option<Self> {
+        match value {
+            Value::Null => Some(None),
+            value => T::from_value(value).map(Some),
+        }
+    }
+
+    fn missing() -> Option<Self> {
+        Some(None)
+    }
+}
+
+/// The arguments of a call, converted one by one
+struct Arguments {
+    values: std::vec::IntoIter<Value>,
+    index: usize,
+}
+
+impl Arguments {
+    /// Checks the number of arguments against the signature, optional ones being last (see `optional_last`)
+    fn new(values: Vec<Value>, types: &[String]) -> Result<Self, String> {
+        let required = types.iter().filter(|kind| !kind.ends_with('?')).count();
+        let expected = match (required, types.len()) {
+            (1, 1) => "1 argument".to_string(),
+            (required, total) if required == total => format!("{} arguments", total),
+            (required, total) => format!("{} to {} arguments", required, total),
+        };
+        if values.len() < required || values.len() > types.len() {
+            return Err(format!("expected {}, got {}", expected, values.len()));
+        }
+
+        Ok(Arguments {
+            values: values.into_iter(),
+            index: 0,
+        })
+    }
+
+    fn next<T: FromValue>(&mut self) -> Result<T, String> {
+        self.index += 1;
+        let Some(value) = self.values.next() else {
+            return T::missing().ok_or_else(|| format!("argument {} is missing", self.index));
+        };
+        let kind = value.kind();
+
+        T::from_value(value).ok_or_else(|| {
+            format!(
+                "expected {} for argument {}, got {}",
+                T::type_name(),
+                self.index,
+                kind
+            )
+        })
+    }
+}
+
+/// Panics when an optional argument comes before a required one
+fn optional_last(arguments: &[String], return_type: &str) {
+    let optional = arguments.iter().position(|kind| kind.ends_with('?'));
+    if let Some(optional) = optional {
+        assert!(
+            arguments[optional..].iter().all(|kind| kind.ends_with('?')),
+            "optional arguments have to come last, not as in {}",
+            signature(arguments, return_type)
+        );
+    }
+}
+
+macro_rules! into_function {
+    ($($arg:ident),*) => {
+        impl<F, R, $($arg),*> IntoFunction<($($arg,)*)> for F
+        where
+            F: Fn($($arg),*) -> R + Send + Sync + 'static,
+            R: Into<Value> + TypeName,
+            $($arg: FromValue,)*
+        {
+            #[allow(unused_mut, unused_variables)]
+            fn into_function(self) -> TypedFunction {
+                let arguments = vec![$($arg::type_name()),*];
+                let return_type = R::type_name();
+                optional_last(&arguments, &return_type);
+
+                TypedFunction {
+                    arguments: arguments.clone(),
+                    return_type,
+                    call: Arc::new(move |values| {
+                        let mut args = Arguments::new(values, &arguments)?;
+                        Ok(self($(args.next::<$arg>()?),*).into())
+                    }),
+                }
+            }
+        }
+    };
+}
+
+into_function!();
+into_function!(A);
+into_function!(A, B);
+into_function!(A, B, C);
+into_function!(A, B, C, D);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        error::RuntimeError,
+        runtime::{Context, Runtime},
+    };
+
+    fn to_placement(n: i64) -> String {
+        format!("#{}", n)
+    }
+
+    #[test]
+    fn typed_functions() {
+        let mut context = Context::new();
+        context.add_function("toPlacement".to_string(), to_placement);
+        context.add_function("greet".to_string(), || "Hello");
+        context.add_function(
+            "join".to_string(),
+            |items: Vec<String>, separator: Option<String>| {
+                items.join(&separator.unwrap_or_else(|| ", ".to_string()))
+            },
+        );
+        context
+            .0
+            .insert("big".to_string(), Value::from(5_000_000_000i64));
+        context.0.insert("names".to_string(), vec!["a", "b"].into());
+        let runtime = Runtime::new(context);
+
+        assert_eq!(runtime.execute("{toPlacement | 3}").unwrap(), "#3");
+        assert_eq!(
+            runtime.execute("{toPlacement | big}").unwrap(),
+            "#5000000000"
+        );
+        assert_eq!(runtime.execute("{greet}").unwrap(), "Hello");
+        assert_eq!(runtime.execute("{join | names ; \"/\"}").unwrap(), "a/b");
+        assert_eq!(runtime.execute("{join | names}").unwrap(), "a, b");
+
+        let message = |program: &str| match runtime.execute(program) {
+            Err(err @ RuntimeError::Arguments { .. }) => err.to_string(),
+            other => panic!("expected an argument error, got {:?}", other.map(|_| ())),
+        };
+        assert_eq!(
+            message("{toPlacement | \"3\"}"),
+            "Wrong arguments for 'toPlacement': expected Int for argument 1, got String"
+        );
+        assert_eq!(
+            message("{toPlacement}"),
+            "Wrong arguments for 'toPlacement': expected 1 argument, got 0"
+        );
+        assert_eq!(
+            message("{join | names ; \"/\" ; 1}"),
+            "Wrong arguments for 'join': expected 1 to 2 arguments, got 3"
+        );
+
+        let Some(Value::Typed(join)) = runtime.global.get("join") else {
+            panic!("join should be a typed function")
+        };
+        assert_eq!(join.signature(), "(List, String?) -> String");
+        #[cfg(feature = "schema")]
+        assert_eq!(
+            join.schema("Joins text").signature(),
+            "(List, String?) -> String"
+        );
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "optional arguments have to come last, not as in (Int?, String) -> String"
+    )]
+    fn optional_before_required() {
+        let mut context = Context::new();
+        context.add_function("greet".to_string(), |times: Option<i64>, name: String| {
+            name.repeat(times.unwrap_or(1) as usize)
+        });
+    }
+}
@@ -0,0 +1,55 @@
+use crate::runtime::Value;
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// What an async template function returns
+pub type BoxFuture = Pin<Box<dyn Future<Output = Option<Value>> + Send>>;
+
+/// Polls every call of a template at once, so a slow API call doesn't hold back the others.
+///
+/// Executor agnostic, only relies on the waker it is polled with.
+pub(crate) struct JoinAll {
+    calls: Vec<Option<BoxFuture>>,
+    results: Vec<Option<Option<Value>>>,
+}
+
+impl JoinAll {
+    pub(crate) fn new(calls: Vec<BoxFuture>) -> Self {
+        JoinAll {
+            results: calls.iter().map(|_| None).collect(),
+            calls: calls.into_iter().map(Some).collect(),
+        }
+    }
+}
+
+impl Future for JoinAll {
+    type Output = Vec<Option<Value>>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        let mut pending = false;
+
+        for (call, result) in this.calls.iter_mut().zip(this.results.iter_mut()) {
+            let Some(future) = call else {
+                continue;
+            };
+            match future.as_mut().poll(cx) {
+                Poll::Ready(value) => {
+                    *result = Some(value);
+                    // Futures must not be polled again once done
+                    *call = None;
+                }
+                Poll::Pending => pending = true,
+            }
+        }
+
+        if pending {
+            return Poll::Pending;
+        }
+
+        Poll::Ready(this.results.iter_mut().filter_map(Option::take).collect())
+    }
+}
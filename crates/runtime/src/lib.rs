@@ -1 +1,20 @@
+// Lets `#[derive(JtlObject)]` name `::jtl_runtime` from inside the crate too
+extern crate self as jtl_runtime;
+
+pub mod date;
+pub mod error;
+pub mod escape;
+pub mod function;
+pub mod future;
+pub mod locale;
+pub mod object;
+mod output;
+pub mod properties;
+pub mod registry;
+pub mod resolver;
 pub mod runtime;
+#[cfg(feature = "serde")]
+mod serialize;
+pub mod stdlib;
+pub mod template;
+pub mod trace;
@@ -0,0 +1,276 @@
+//! Languages of a render: plural rules, ordinals, number separators and a catalog of messages.
+//!
+//! ```
+//! # use std::sync::Arc;
+//! # use jtl_runtime::{locale::Locale, runtime::{Context, Runtime}, stdlib};
+//! let mut french = Locale::bundled("fr").unwrap();
+//! french.add_message("welcome", "Bienvenue, {name}.").unwrap();
+//!
+//! let mut context = Context::new();
+//! stdlib::register(&mut context);
+//! context.0.insert("name".to_string(), "Nyx".into());
+//! let mut runtime = Runtime::new(context);
+//! runtime.options.locale = Arc::new(french);
+//!
+//! assert_eq!(runtime.execute("{t | \"welcome\"}").unwrap(), "Bienvenue, Nyx.");
+//! assert_eq!(runtime.execute("{toPlacement | 1}").unwrap(), "1er");
+//! ```
+
+use crate::{error::RuntimeError, runtime::Value, template::Template};
+use std::{collections::HashMap, sync::Arc};
+
+/// CLDR plural category of a number, <https://cldr.unicode.org/index/cldr-spec/plural-rules>
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PluralCategory {
+    Zero,
+    One,
+    Two,
+    Few,
+    Many,
+    Other,
+}
+
+/// Category of a count, picks the form of `pluralize`
+pub type PluralRule = fn(f64) -> PluralCategory;
+
+/// Aka: 1 -> "1st" in English, "1er" in French
+pub type OrdinalRule = fn(i64) -> String;
+
+/// The tags `Locale::bundled` has data for
+pub const BUNDLED: &[&str] = &["en", "fr", "de", "es", "ru", "pl"];
+
+/// How a language writes numbers and counts, and its messages. English by default.
+#[derive(Debug, Clone)]
+pub struct Locale {
+    /// BCP 47 tag, aka: "en" or "pt-BR"
+    pub tag: String,
+    pub decimal_separator: String,
+    /// Between groups of thousands
+    pub group_separator: String,
+    /// Digits before the first separator for a number to be grouped at all,
+    /// aka: 2 in Spanish which writes "1234" but "12.345"
+    pub min_grouping: usize,
+    pub plural_rule: PluralRule,
+    pub ordinal_rule: OrdinalRule,
+    messages: HashMap<String, Arc<Template>>,
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        Locale::bundled("en").expect("English is bundled")
+    }
+}
+
+impl Locale {
+    /// The data bundled for one of `BUNDLED`, without messages. Regions are ignored, aka: "fr-CA" is "fr"
+    pub fn bundled(tag: &str) -> Option<Self> {
+        let language = tag.split(['-', '_']).next().unwrap_or(tag);
+        let (decimal, group, min_grouping, plural_rule, ordinal_rule): (
+            _,
+            _,
+            _,
+            PluralRule,
+            OrdinalRule,
+        ) = match language.to_ascii_lowercase().as_str() {
+            "en" => (".", ",", 1, plural_one, ordinal_en),
+            // Narrow no-break space
+            "fr" => (",", "\u{202F}", 1, plural_fr, ordinal_fr),
+            "de" => (",", ".", 1, plural_one, ordinal_dot),
+            "es" => (",", ".", 2, plural_one, |n| format!("{}.º", n)),
+            "ru" => (",", "\u{A0}", 1, plural_slavic, |n| format!("{}-й", n)),
+            "pl" => (",", "\u{A0}", 2, plural_pl, ordinal_dot),
+            _ => return None,
+        };
+
+        Some(Locale {
+            tag: tag.to_string(),
+            decimal_separator: decimal.to_string(),
+            group_separator: group.to_string(),
+            min_grouping,
+            plural_rule,
+            ordinal_rule,
+            messages: HashMap::new(),
+        })
+    }
+
+    pub fn plural(&self, count: f64) -> PluralCategory {
+        (self.plural_rule)(count)
+    }
+
+    pub fn ordinal(&self, number: i64) -> String {
+        (self.ordinal_rule)(number)
+    }
+
+    /// Groups the thousands of an `Int` or finite `Float`, with the separators of the locale
+    pub fn format_number(&self, value: &Value) -> Option<String> {
+        let number = match value {
+            Value::Int(int) => int.to_string(),
+            Value::Float(float) if float.is_finite() => float.to_string(),
+            _ => return None,
+        };
+        let (sign, unsigned) = match number.strip_prefix('-') {
+            Some(unsigned) => ("-", unsigned),
+            None => ("", number.as_str()),
+        };
+        let (integer, fraction) = match unsigned.split_once('.') {
+            Some((integer, fraction)) => (integer, Some(fraction)),
+            None => (unsigned, None),
+        };
+
+        let mut grouped = String::from(sign);
+        let groups = integer.len() >= 3 + self.min_grouping;
+        for (index, digit) in integer.chars().enumerate() {
+            if groups && index > 0 && (integer.len() - index) % 3 == 0 {
+                grouped.push_str(&self.group_separator);
+            }
+            grouped.push(digit);
+        }
+        if let Some(fraction) = fraction {
+            grouped.push_str(&self.decimal_separator);
+            grouped.push_str(fraction);
+        }
+
+        Some(grouped)
+    }
+
+    /// Adds a message to the catalog, a template rendered by `{t | "key"}` against the context of the render
+    pub fn add_message(&mut self, key: &str, source: &str) -> Result<(), Vec<RuntimeError>> {
+        let template = Template::compile(source)?;
+        self.messages.insert(key.to_string(), Arc::new(template));
+        Ok(())
+    }
+
+    pub fn message(&self, key: &str) -> Option<&Template> {
+        self.messages.get(key).map(Arc::as_ref)
+    }
+}
+
+/// Whole numbers only, the fraction digits of a float can't be told apart from "1.0"
+fn whole(count: f64) -> Option<u64> {
+    (count.fract() == 0.0 && count >= 0.0 && count < u64::MAX as f64).then_some(count as u64)
+}
+
+/// English, German, Spanish...
+fn plural_one(count: f64) -> PluralCategory {
+    match whole(count.abs()) {
+        Some(1) => PluralCategory::One,
+        _ => PluralCategory::Other,
+    }
+}
+
+/// 0 and 1 (and anything in between) are singular in French
+fn plural_fr(count: f64) -> PluralCategory {
+    if count.abs() < 2.0 {
+        PluralCategory::One
+    } else {
+        PluralCategory::Other
+    }
+}
+
+/// Russian, and Ukrainian or Belarusian if they were bundled
+fn plural_slavic(count: f64) -> PluralCategory {
+    let Some(n) = whole(count.abs()) else {
+        return PluralCategory::Other;
+    };
+    match (n % 10, n % 100) {
+        (1, 11) => PluralCategory::Many,
+        (1, _) => PluralCategory::One,
+        (2..=4, 12..=14) => PluralCategory::Many,
+        (2..=4, _) => PluralCategory::Few,
+        _ => PluralCategory::Many,
+    }
+}
+
+fn plural_pl(count: f64) -> PluralCategory {
+    match whole(count.abs()) {
+        Some(1) => PluralCategory::One,
+        Some(n) if matches!(n % 10, 2..=4) && !matches!(n % 100, 12..=14) => PluralCategory::Few,
+        Some(_) => PluralCategory::Many,
+        None => PluralCategory::Other,
+    }
+}
+
+fn ordinal_en(number: i64) -> String {
+    let suffix = match (number.unsigned_abs() % 10, number.unsigned_abs() % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{}{}", number, suffix)
+}
+
+fn ordinal_fr(number: i64) -> String {
+    match number {
+        1 => "1er".to_string(),
+        number => format!("{}e", number),
+    }
+}
+
+fn ordinal_dot(number: i64) -> String {
+    format!("{}.", number)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use PluralCategory::*;
+
+    #[test]
+    fn bundled_locales() {
+        let locale = |tag: &str| Locale::bundled(tag).unwrap();
+        assert!(BUNDLED.iter().all(|tag| Locale::bundled(tag).is_some()));
+        assert!(Locale::bundled("tlh").is_none());
+
+        let plurals = |tag: &str, counts: &[f64]| -> Vec<PluralCategory> {
+            let locale = locale(tag);
+            counts.iter().map(|count| locale.plural(*count)).collect()
+        };
+        assert_eq!(
+            plurals("en", &[0.0, 1.0, 1.5, 2.0]),
+            [Other, One, Other, Other]
+        );
+        assert_eq!(
+            plurals("fr-CA", &[0.0, 1.0, 1.5, 2.0]),
+            [One, One, One, Other]
+        );
+        assert_eq!(
+            plurals("ru", &[1.0, 3.0, 5.0, 11.0, 21.0, 22.0, 112.0, 1.5]),
+            [One, Few, Many, Many, One, Few, Many, Other]
+        );
+        assert_eq!(
+            plurals("pl", &[1.0, 2.0, 5.0, 21.0, 24.0]),
+            [One, Few, Many, Many, Few]
+        );
+
+        assert_eq!(locale("en").ordinal(112), "112th");
+        assert_eq!(locale("fr").ordinal(1), "1er");
+        assert_eq!(locale("de").ordinal(3), "3.");
+        assert_eq!(locale("ru").ordinal(2), "2-й");
+
+        let number = Value::Float(-1234567.5);
+        assert_eq!(locale("en").format_number(&number).unwrap(), "-1,234,567.5");
+        assert_eq!(locale("de").format_number(&number).unwrap(), "-1.234.567,5");
+        assert_eq!(
+            locale("fr").format_number(&Value::Int(1234)).unwrap(),
+            "1\u{202F}234"
+        );
+        assert_eq!(
+            locale("es").format_number(&Value::Int(1234)).unwrap(),
+            "1234"
+        );
+        assert_eq!(
+            locale("es").format_number(&Value::Int(-12345)).unwrap(),
+            "-12.345"
+        );
+        assert_eq!(
+            locale("pl").format_number(&Value::Float(1234.5)).unwrap(),
+            "1234,5"
+        );
+        assert_eq!(
+            locale("pl").format_number(&number).unwrap(),
+            "-1\u{A0}234\u{A0}567,5"
+        );
+    }
+}
@@ -0,0 +1,199 @@
+//! Rust structs as template objects, see `#[derive(JtlObject)]` (the "derive" feature).
+//!
+//! ```
+//! # #[cfg(feature = "derive")]
+//! # {
+//! use jtl_runtime::{object::JtlObject, runtime::{Context, Runtime}};
+//!
+//! #[derive(JtlObject)]
+//! struct Guild {
+//!     name: String,
+//!     #[jtl(rename = "memberCount", description = "Members, bots included")]
+//!     member_count: u32,
+//!     #[jtl(skip)]
+//!     webhook: String,
+//! }
+//!
+//! let guild = Guild {
+//!     name: "BarFight".to_string(),
+//!     member_count: 7,
+//!     webhook: "https://...".to_string(),
+//! };
+//! let mut context = Context::new();
+//! context.add_object("guild".to_string(), guild.into_object());
+//!
+//! let runtime = Runtime::new(context);
+//! assert_eq!(runtime.execute("{guild.name}: {guild.memberCount}").unwrap(), "BarFight: 7");
+//! # }
+//! ```
+
+use crate::function::TypeName;
+use crate::runtime::ObjectHashMap;
+#[cfg(feature = "derive")]
+pub use jtl_derive::JtlObject;
+#[cfg(feature = "schema")]
+use jtl_service::{SchemaService, StructuresMidd};
+#[cfg(feature = "schema")]
+use std::collections::HashMap;
+
+/// A struct that renders as an object, derived with `#[derive(JtlObject)]`.
+///
+/// The derive also makes it a `Value` with `From`, and describes it (and the objects of its fields)
+/// through `TypeName::objects`. Generic structs are objects for any field types that are values:
+///
+/// ```
+/// # #[cfg(feature = "derive")]
+/// # {
+/// use jtl_runtime::{object::JtlObject, runtime::Value};
+///
+/// #[derive(JtlObject)]
+/// struct Page<T> {
+///     items: Vec<T>,
+///     total: u32,
+/// }
+///
+/// let page = Page { items: vec!["Nyx", "Kit"], total: 2 };
+/// assert!(matches!(page.into_object()["items"], Value::List(_)));
+/// # }
+/// ```
+pub trait JtlObject: TypeName {
+    fn into_object(self) -> ObjectHashMap;
+}
+
+/// An object as the schema knows it
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjectSchema {
+    /// Name of the structure, its type is "#" followed by it
+    pub name: &'static str,
+    pub description: Option<&'static str>,
+    pub fields: Vec<FieldSchema>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldSchema {
+    pub name: &'static str,
+    pub description: Option<&'static str>,
+    /// Aka: "String", "#User" or "Int?"
+    pub type_name: String,
+}
+
+/// Every object `T` is made of, in order of appearance
+pub fn objects<T: JtlObject>() -> Vec<ObjectSchema> {
+    let mut objects = Vec::new();
+    T::objects(&mut objects);
+    objects
+}
+
+/// The `structures` of `T` and the objects of its fields, merge it into the schema of the context
+#[cfg(feature = "schema")]
+pub fn schema<T: JtlObject>() -> SchemaService {
+    let mut structures = HashMap::new();
+    for object in objects::<T>() {
+        let mut fields = Vec::new();
+        if let Some(description) = object.description {
+            fields.push(StructuresMidd(
+                ":description".to_string(),
+                vec![description.to_string()],
+            ));
+        }
+        for field in object.fields {
+            let mut value = vec![field.type_name];
+            value.extend(field.description.map(str::to_string));
+            fields.push(StructuresMidd(field.name.to_string(), value));
+        }
+        structures.insert(object.name.to_string(), fields);
+    }
+
+    SchemaService {
+        v: "1.0.0".to_string(),
+        global: Vec::new(),
+        structures,
+        functions: HashMap::new(),
+    }
+}
+
+#[cfg(all(test, feature = "derive"))]
+mod tests {
+    use super::*;
+    use crate::runtime::{Context, Runtime, Value};
+
+    #[derive(JtlObject)]
+    #[jtl(rename = "User", description = "Someone on Discord")]
+    struct Member {
+        #[jtl(rename = "displayName")]
+        display_name: String,
+        #[jtl(description = "Who invited them, if anyone")]
+        inviter: Option<String>,
+    }
+
+    #[derive(JtlObject)]
+    struct Guild {
+        name: String,
+        owner: Member,
+        members: Vec<Member>,
+        #[jtl(skip)]
+        #[allow(dead_code)]
+        token: String,
+    }
+
+    #[test]
+    fn derived_objects() {
+        let member = |name: &str| Member {
+            display_name: name.to_string(),
+            inviter: None,
+        };
+        let guild = Guild {
+            name: "BarFight".to_string(),
+            owner: member("Nyx"),
+            members: vec![member("Nyx"), member("Kit")],
+            token: "secret".to_string(),
+        };
+
+        let mut context = Context::new();
+        context.add_object("guild".to_string(), guild.into_object());
+        let runtime = Runtime::new(context);
+        assert_eq!(
+            runtime
+                .execute("{guild.name}/{guild.owner.displayName}/{guild.members.length}")
+                .unwrap(),
+            "BarFight/Nyx/2"
+        );
+        assert!(runtime.execute("{guild.token}").is_err());
+        assert!(matches!(Value::from(member("Kit")), Value::Object(_)));
+
+        let objects = objects::<Guild>();
+        assert_eq!(
+            objects.iter().map(|object| object.name).collect::<Vec<_>>(),
+            vec!["Guild", "User"]
+        );
+        let types = |index: usize| -> Vec<(&str, &str)> {
+            let fields = &objects[index].fields;
+            fields
+                .iter()
+                .map(|field| (field.name, field.type_name.as_str()))
+                .collect()
+        };
+        assert_eq!(
+            types(0),
+            vec![("name", "String"), ("owner", "#User"), ("members", "List")]
+        );
+        assert_eq!(
+            types(1),
+            vec![("displayName", "String"), ("inviter", "String?")]
+        );
+
+        #[cfg(feature = "schema")]
+        {
+            let schema = schema::<Guild>();
+            let user = &schema.structures["User"];
+            assert_eq!(user[0].1, vec!["Someone on Discord"]);
+            assert_eq!(
+                user[2].1,
+                vec![
+                    "String?".to_string(),
+                    "Who invited them, if anyone".to_string()
+                ]
+            );
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use std::{fmt, io};
+
+/// Where a render writes to, refuses to go over `Limits::max_output_bytes`
+pub(crate) struct Output<'w, W: fmt::Write> {
+    inner: &'w mut W,
+    written: usize,
+    limit: Option<usize>,
+    /// Set when a write failed because of the limit, rather than because of `inner`
+    pub(crate) over_limit: bool,
+}
+
+impl<'w, W: fmt::Write> Output<'w, W> {
+    pub(crate) fn new(inner: &'w mut W, limit: Option<usize>) -> Self {
+        Output {
+            inner,
+            written: 0,
+            limit,
+            over_limit: false,
+        }
+    }
+
+    pub(crate) fn limit(&self) -> Option<usize> {
+        self.limit
+    }
+
+    /// Bytes written so far
+    pub(crate) fn written(&self) -> usize {
+        self.written
+    }
+}
+
+impl<W: fmt::Write> fmt::Write for Output<'_, W> {
+    fn write_str(&mut self, text: &str) -> fmt::Result {
+        if let Some(limit) = self.limit {
+            if self.written + text.len() > limit {
+                self.over_limit = true;
+                return Err(fmt::Error);
+            }
+        }
+        self.written += text.len();
+        self.inner.write_str(text)
+    }
+}
+
+/// Lets a render write to an `io::Write`, keeping the error `fmt::Write` has no room for
+pub(crate) struct IoWriter<'w, W: io::Write> {
+    inner: &'w mut W,
+    pub(crate) error: Option<io::Error>,
+}
+
+impl<'w, W: io::Write> IoWriter<'w, W> {
+    pub(crate) fn new(inner: &'w mut W) -> Self {
+        IoWriter { inner, error: None }
+    }
+}
+
+impl<W: io::Write> fmt::Write for IoWriter<'_, W> {
+    fn write_str(&mut self, text: &str) -> fmt::Result {
+        self.inner.write_all(text.as_bytes()).map_err(|err| {
+            self.error = Some(err);
+            fmt::Error
+        })
+    }
+}
@@ -0,0 +1,280 @@
+//! Properties every value of a type has, aka: "{user.name.length}" or "{count.isEven}".
+//!
+//! They are looked up when traversing anything but an object, so objects can't have them
+//! and the fields of an object always win.
+
+use crate::locale::Locale;
+use crate::runtime::Value;
+use crate::stdlib;
+#[cfg(feature = "schema")]
+use jtl_service::{SchemaService, StructuresMidd};
+#[cfg(feature = "schema")]
+use std::collections::HashMap;
+
+/// A property of a type, with what the schema says about it
+#[cfg_attr(not(feature = "schema"), allow(dead_code))]
+struct Property {
+    /// `Value::kind` of the values having it
+    kind: &'static str,
+    name: &'static str,
+    description: &'static str,
+    return_type: &'static str,
+    get: fn(&Value, &Locale) -> Option<Value>,
+}
+
+const PROPERTIES: &[Property] = &[
+    Property {
+        kind: "String",
+        name: "length",
+        description: "Number of characters",
+        return_type: "Int",
+        get: |value, _| call(stdlib::length, value),
+    },
+    Property {
+        kind: "String",
+        name: "upper",
+        description: "The text in uppercase",
+        return_type: "String",
+        get: |value, _| call(stdlib::upper, value),
+    },
+    Property {
+        kind: "String",
+        name: "lower",
+        description: "The text in lowercase",
+        return_type: "String",
+        get: |value, _| call(stdlib::lower, value),
+    },
+    Property {
+        kind: "String",
+        name: "trim",
+        description: "The text without the whitespace around it",
+        return_type: "String",
+        get: |value, _| call(stdlib::trim, value),
+    },
+    Property {
+        kind: "String",
+        name: "isEmpty",
+        description: "Whether there is no text",
+        return_type: "Bool",
+        get: |value, _| match value {
+            Value::String(string) => Some(Value::Bool(string.is_empty())),
+            _ => None,
+        },
+    },
+    Property {
+        kind: "Int",
+        name: "isEven",
+        description: "Whether the number is even",
+        return_type: "Bool",
+        get: |value, _| match value {
+            Value::Int(int) => Some(Value::Bool(int % 2 == 0)),
+            _ => None,
+        },
+    },
+    Property {
+        kind: "Int",
+        name: "isOdd",
+        description: "Whether the number is odd",
+        return_type: "Bool",
+        get: |value, _| match value {
+            Value::Int(int) => Some(Value::Bool(int % 2 != 0)),
+            _ => None,
+        },
+    },
+    Property {
+        kind: "Int",
+        name: "ordinal",
+        description: "The number as a placement, aka: 3 -> \"3rd\"",
+        return_type: "String",
+        get: |value, locale| stdlib::ordinal(vec![value.clone()], locale),
+    },
+    Property {
+        kind: "Int",
+        name: "formatted",
+        description: "The number with thousands separators, aka: 1234 -> \"1,234\"",
+        return_type: "String",
+        get: |value, locale| stdlib::format_number(vec![value.clone()], locale),
+    },
+    Property {
+        kind: "Float",
+        name: "formatted",
+        description: "The number with thousands separators, aka: 1234.5 -> \"1,234.5\"",
+        return_type: "String",
+        get: |value, locale| stdlib::format_number(vec![value.clone()], locale),
+    },
+    Property {
+        kind: "Float",
+        name: "rounded",
+        description: "The number rounded to the nearest whole number",
+        return_type: "Float",
+        get: |value, _| call(stdlib::round, value),
+    },
+    Property {
+        kind: "List",
+        name: "length",
+        description: "Number of items",
+        return_type: "Int",
+        get: |value, _| call(stdlib::length, value),
+    },
+    Property {
+        kind: "List",
+        name: "first",
+        description: "The first item, null when the list is empty",
+        return_type: "Any",
+        get: |value, _| match value {
+            Value::List(items) => Some(items.first().cloned().into()),
+            _ => None,
+        },
+    },
+    Property {
+        kind: "List",
+        name: "last",
+        description: "The last item, null when the list is empty",
+        return_type: "Any",
+        get: |value, _| match value {
+            Value::List(items) => Some(items.last().cloned().into()),
+            _ => None,
+        },
+    },
+    Property {
+        kind: "List",
+        name: "isEmpty",
+        description: "Whether there are no items",
+        return_type: "Bool",
+        get: |value, _| match value {
+            Value::List(items) => Some(Value::Bool(items.is_empty())),
+            _ => None,
+        },
+    },
+    Property {
+        kind: "Date",
+        name: "unix",
+        description: "Seconds since the Unix epoch",
+        return_type: "Int",
+        get: |value, _| match value {
+            Value::Date(timestamp) => Some(timestamp.unix().into()),
+            _ => None,
+        },
+    },
+    Property {
+        kind: "Date",
+        name: "year",
+        description: "The year, in UTC",
+        return_type: "Int",
+        get: |value, _| match value {
+            Value::Date(timestamp) => Some(timestamp.date_time().year.into()),
+            _ => None,
+        },
+    },
+];
+
+/// Hands the value to a function of the standard library
+fn call(func: fn(Vec<Value>) -> Option<Value>, value: &Value) -> Option<Value> {
+    func(vec![value.clone()])
+}
+
+/// The property `name` of `value`, when its type has one. Aka: "ordinal" is written as `locale` wants it
+pub fn get(value: &Value, name: &str, locale: &Locale) -> Option<Value> {
+    let kind = value.kind();
+    let property = PROPERTIES
+        .iter()
+        .find(|property| property.kind == kind && property.name == name)?;
+
+    (property.get)(value, locale)
+}
+
+/// Describes the properties for the language server, as structures named after their type,
+/// so a field typed "String" (without a '#') completes with the properties of strings.
+#[cfg(feature = "schema")]
+pub fn schema() -> SchemaService {
+    let mut structures: HashMap<String, Vec<StructuresMidd>> = HashMap::new();
+    for property in PROPERTIES {
+        structures
+            .entry(property.kind.to_string())
+            .or_default()
+            .push(StructuresMidd(
+                property.name.to_string(),
+                vec![
+                    property.return_type.to_string(),
+                    property.description.to_string(),
+                ],
+            ));
+    }
+
+    SchemaService {
+        v: "1.0.0".to_string(),
+        global: Vec::new(),
+        structures,
+        functions: HashMap::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        date::Timestamp,
+        error::RuntimeError,
+        runtime::{Context, ObjectHashMap, Runtime},
+    };
+    use std::collections::HashMap;
+
+    #[test]
+    fn primitive_properties() {
+        let mut context = Context::new();
+        let mut user_object: ObjectHashMap = HashMap::new();
+        user_object.insert("name".to_string(), "Nyx".into());
+        user_object.insert("count".to_string(), 1234u32.into());
+        user_object.insert("balance".to_string(), (-4i64).into());
+        user_object.insert("roles".to_string(), vec!["Admin", "Mod"].into());
+        user_object.insert(
+            "joined".to_string(),
+            Timestamp::from_unix(1_700_000_000).into(),
+        );
+        // Fields of objects are never shadowed
+        user_object.insert("length".to_string(), "tall".into());
+        context.add_object("user".to_string(), user_object);
+        let runtime = Runtime::new(context);
+        let execute = |program: &str| runtime.execute(program).unwrap();
+
+        assert_eq!(execute("{user.name.length}"), "3");
+        assert_eq!(execute("{user.name.upper.length.isOdd}"), "true");
+        assert_eq!(
+            execute("{user.count.isEven}/{user.count.formatted}"),
+            "true/1,234"
+        );
+        assert_eq!(execute("{user.count.ordinal}"), "1234th");
+        assert_eq!(
+            execute("{user.balance.isEven}/{user.balance.ordinal}/{user.balance.formatted}"),
+            "true/-4th/-4"
+        );
+        assert!(matches!(Value::from(u64::MAX), Value::Float(_)));
+        let french = Locale::bundled("fr").unwrap();
+        assert!(matches!(
+            get(&Value::Int(1), "ordinal", &french),
+            Some(Value::String(ordinal)) if ordinal == "1er"
+        ));
+        assert_eq!(
+            execute("{user.roles.first}, {user.roles.length}"),
+            "Admin, 2"
+        );
+        assert_eq!(execute("{user.joined.year}"), "2023");
+        assert_eq!(execute("{user.length}"), "tall");
+
+        assert!(matches!(
+            runtime.execute("{user.name.isEven}"),
+            Err(RuntimeError::NotTraversable { kind: "String", .. })
+        ));
+        assert!(matches!(
+            get(&Value::List(Vec::new()), "first", &Locale::default()),
+            Some(Value::Null)
+        ));
+
+        #[cfg(feature = "schema")]
+        {
+            let schema = schema();
+            assert_eq!(schema.structures["Int"].len(), 4);
+            assert_eq!(schema.structures["List"][1].0, "first");
+        }
+    }
+}
@@ -0,0 +1,245 @@
+use crate::{error::RuntimeError, template::Template};
+use std::{
+    collections::HashMap,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::SystemTime,
+};
+
+/// Why a template file could not be (re)loaded
+#[derive(Debug)]
+pub enum RegistryError {
+    Io {
+        path: PathBuf,
+        error: io::Error,
+    },
+    /// `source` is what was read from the file, for `RuntimeError::render`
+    Syntax {
+        path: PathBuf,
+        source: String,
+        errors: Vec<RuntimeError>,
+    },
+}
+
+impl RegistryError {
+    pub fn path(&self) -> &Path {
+        match self {
+            RegistryError::Io { path, .. } | RegistryError::Syntax { path, .. } => path,
+        }
+    }
+}
+
+impl fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegistryError::Io { path, error } => write!(f, "{}: {}", path.display(), error),
+            RegistryError::Syntax {
+                path,
+                source,
+                errors,
+            } => {
+                write!(f, "{}:", path.display())?;
+                for err in errors {
+                    write!(f, "\n{}", err.render(source))?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for RegistryError {}
+
+struct Entry {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    template: Arc<Template>,
+}
+
+/// The `*.jtl` templates of a directory, compiled and cached by file name. Aka: "welcome.jtl" -> "welcome"
+///
+/// Files are only read again by `reload`, and only when they were modified since.
+/// Subdirectories are not looked into.
+///
+/// ```no_run
+/// # use jtl_runtime::{registry::TemplateRegistry, runtime::{Context, Runtime}};
+/// let mut registry = TemplateRegistry::new("templates");
+/// for err in registry.reload().unwrap() {
+///     eprintln!("{}", err);
+/// }
+///
+/// let runtime = Runtime::new(Context::new());
+/// let welcome = registry.get("welcome").unwrap();
+/// println!("{}", runtime.render(&welcome).unwrap());
+/// ```
+pub struct TemplateRegistry {
+    dir: PathBuf,
+    templates: HashMap<String, Entry>,
+}
+
+impl TemplateRegistry {
+    /// An empty registry of `dir`, `reload` loads its templates
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        TemplateRegistry {
+            dir: dir.into(),
+            templates: HashMap::new(),
+        }
+    }
+
+    /// Compiles the templates that are new or were modified since the last reload,
+    /// and forgets the ones that were deleted.
+    ///
+    /// A file that fails to load is reported in the returned list without affecting the others.
+    /// If it was loaded before, its last working version is kept.
+    /// Only fails when the directory itself can't be read.
+    pub fn reload(&mut self) -> io::Result<Vec<RegistryError>> {
+        let mut errors = Vec::new();
+        let mut found: Vec<String> = Vec::new();
+
+        for dir_entry in fs::read_dir(&self.dir)? {
+            let path = dir_entry?.path();
+            if !path.is_file() || path.extension().is_none_or(|ext| ext != "jtl") {
+                continue;
+            }
+            let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+            let name = name.to_string();
+            found.push(name.clone());
+
+            let modified = fs::metadata(&path).and_then(|meta| meta.modified()).ok();
+            if let Some(entry) = self.templates.get(&name) {
+                // Without a modification time there is no telling, so it's always read again
+                if modified.is_some() && entry.modified == modified {
+                    continue;
+                }
+            }
+
+            match load(&path) {
+                Ok(template) => {
+                    self.templates.insert(
+                        name,
+                        Entry {
+                            path,
+                            modified,
+                            template: Arc::new(template),
+                        },
+                    );
+                }
+                Err(err) => errors.push(err),
+            }
+        }
+
+        self.templates.retain(|name, _| found.contains(name));
+
+        Ok(errors)
+    }
+
+    pub fn get(&self, name: &str) -> Option<Arc<Template>> {
+        self.templates
+            .get(name)
+            .map(|entry| Arc::clone(&entry.template))
+    }
+
+    /// File the template was loaded from
+    pub fn path(&self, name: &str) -> Option<&Path> {
+        self.templates.get(name).map(|entry| entry.path.as_path())
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.templates.keys().map(String::as_str)
+    }
+}
+
+fn load(path: &Path) -> Result<Template, RegistryError> {
+    let source = fs::read_to_string(path).map_err(|error| RegistryError::Io {
+        path: path.to_path_buf(),
+        error,
+    })?;
+
+    Template::compile(&source).map_err(|errors| RegistryError::Syntax {
+        path: path.to_path_buf(),
+        source,
+        errors,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::{Context, ObjectHashMap};
+    use std::{collections::HashMap, fs::File, time::Duration};
+
+    fn write(path: &Path, content: &str, modified: SystemTime) {
+        fs::write(path, content).unwrap();
+        File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+    }
+
+    #[test]
+    fn load_and_reload() {
+        let dir = std::env::temp_dir().join(format!("jtl_registry_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let time = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+
+        write(&dir.join("welcome.jtl"), "Welcome to {guild.name}", time);
+        write(&dir.join("broken.jtl"), "Bye {guild.", time);
+        write(&dir.join("notes.txt"), "{not a template", time);
+
+        let mut context = Context::new();
+        let mut guild_object: ObjectHashMap = HashMap::new();
+        guild_object.insert("name".to_string(), "BarFight".into());
+        context.add_object("guild".to_string(), guild_object);
+
+        let mut registry = TemplateRegistry::new(&dir);
+        let errors = registry.reload().unwrap();
+        assert_eq!(registry.names().collect::<Vec<_>>(), vec!["welcome"]);
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(&errors[0], RegistryError::Syntax { .. }));
+        assert_eq!(errors[0].path(), dir.join("broken.jtl"));
+
+        let welcome = registry.get("welcome").unwrap();
+        assert_eq!(welcome.render(&context).unwrap(), "Welcome to BarFight");
+
+        // Unchanged files are not compiled again
+        assert_eq!(registry.reload().unwrap().len(), 1);
+        assert!(Arc::ptr_eq(&welcome, &registry.get("welcome").unwrap()));
+
+        write(
+            &dir.join("welcome.jtl"),
+            "Hi {guild.name}",
+            time + Duration::from_secs(1),
+        );
+        write(
+            &dir.join("broken.jtl"),
+            "Bye {guild.name}",
+            time + Duration::from_secs(1),
+        );
+        assert!(registry.reload().unwrap().is_empty());
+        let render = |name: &str| registry.get(name).unwrap().render(&context).unwrap();
+        assert_eq!(render("welcome"), "Hi BarFight");
+        assert_eq!(render("broken"), "Bye BarFight");
+
+        // A broken edit keeps the last working version
+        write(
+            &dir.join("welcome.jtl"),
+            "Hi {guild",
+            time + Duration::from_secs(2),
+        );
+        fs::remove_file(dir.join("broken.jtl")).unwrap();
+        assert_eq!(registry.reload().unwrap().len(), 1);
+        assert_eq!(
+            registry.get("welcome").unwrap().render(&context).unwrap(),
+            "Hi BarFight"
+        );
+        assert!(registry.get("broken").is_none());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,103 @@
+use crate::runtime::{Context, ObjectHashMap, Value};
+use std::{borrow::Cow, collections::HashMap};
+
+/// Where the values of a render come from, looked up one segment of a path at a time.
+///
+/// Only what a template references is ever resolved, aka: "{user.name}" asks the root resolver
+/// for "user", then the `Value::Lazy` it returns for "name". Values can so be fetched from a cache
+/// when needed, instead of filling a whole `Context` before every render.
+pub trait Resolver: Send + Sync {
+    fn get(&self, segment: &str) -> Option<Value>;
+
+    /// Same as `get`, without cloning when the value is already owned by the resolver
+    fn lookup(&self, segment: &str) -> Option<Cow<'_, Value>> {
+        self.get(segment).map(Cow::Owned)
+    }
+}
+
+impl Resolver for Context {
+    fn get(&self, segment: &str) -> Option<Value> {
+        self.0.get(segment).cloned()
+    }
+
+    fn lookup(&self, segment: &str) -> Option<Cow<'_, Value>> {
+        self.0.get(segment).map(Cow::Borrowed)
+    }
+}
+
+impl Resolver for ObjectHashMap {
+    fn get(&self, segment: &str) -> Option<Value> {
+        HashMap::get(self, segment).cloned()
+    }
+
+    fn lookup(&self, segment: &str) -> Option<Cow<'_, Value>> {
+        HashMap::get(self, segment).map(Cow::Borrowed)
+    }
+}
+
+/// Aka: `|segment: &str| cache.user(segment)`
+impl<F> Resolver for F
+where
+    F: Fn(&str) -> Option<Value> + Send + Sync,
+{
+    fn get(&self, segment: &str) -> Option<Value> {
+        self(segment)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        error::RuntimeError,
+        runtime::{Runtime, UndefinedMode},
+        template::Template,
+    };
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    #[test]
+    fn lazy_resolvers() {
+        let lookups = Arc::new(AtomicUsize::new(0));
+        let counted = Arc::clone(&lookups);
+        let user = Value::lazy(move |segment: &str| {
+            counted.fetch_add(1, Ordering::SeqCst);
+            match segment {
+                "name" => Some("Nyx".into()),
+                "id" => Some(42u32.into()),
+                _ => None,
+            }
+        });
+        let root = move |segment: &str| match segment {
+            "user" => Some(user.clone()),
+            "upper" => Some(Value::function(|args| match args.first() {
+                Some(Value::String(text)) => Some(text.to_uppercase().into()),
+                _ => None,
+            })),
+            _ => None,
+        };
+
+        let template = Template::compile("{upper | user.name}").unwrap();
+        assert_eq!(template.render(&root).unwrap(), "NYX");
+        assert_eq!(lookups.load(Ordering::SeqCst), 1);
+
+        let template = Template::compile("{user.name}#{user.id}").unwrap();
+        assert_eq!(template.render(&root).unwrap(), "Nyx#42");
+        assert_eq!(lookups.load(Ordering::SeqCst), 3);
+
+        assert!(matches!(
+            Template::compile("{user.email}").unwrap().render(&root),
+            Err(RuntimeError::UndefinedProperty { path, .. }) if path == ["user", "email"]
+        ));
+        assert!(matches!(
+            Template::compile("{user}").unwrap().render(&root),
+            Err(RuntimeError::Unrenderable { kind: "Object", .. })
+        ));
+
+        let mut runtime = Runtime::new(root);
+        runtime.options.undefined = UndefinedMode::Empty;
+        assert_eq!(runtime.execute("{user.name}/{user.email}").unwrap(), "Nyx/");
+    }
+}
@@ -1,19 +1,322 @@
+use crate::date::Timestamp;
+use crate::error::RuntimeError;
+use crate::escape::Escape;
+use crate::function::{IntoFunction, TypedFunction};
+use crate::future::{BoxFuture, JoinAll};
+use crate::locale::Locale;
+use crate::output::Output;
+use crate::properties;
+use crate::resolver::Resolver;
+use crate::template::{Rendered, Template};
+use crate::trace::{Segment, SegmentKind, Traced};
 use jtl_parser::{
-    lex::Lexer,
-    parser::{Arg, Expression, PValue, Parser, Source, Stat},
+    lex::{PPosition, Span},
+    parser::{Arg, Expression, PValue, Property, Stat},
 };
-use std::collections::HashMap;
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    fmt::{self, Write},
+    future::Future,
+    io,
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
+};
+
+pub type ObjectHashMap = HashMap<String, Value>;
+
+/// A function callable from a template. Owned and shareable, so it may capture its environment
+/// (a database pool, per-guild config...) as long as it is `Send + Sync`.
+pub type Function = Arc<dyn Fn(Vec<Value>) -> Option<Value> + Send + Sync>;
 
-pub type ObjectHashMap = HashMap<String, Value<'static>>;
+/// A function that has to wait on something (a database, an API...) before returning.
+/// Only callable when rendering with `Runtime::render_async`.
+pub type AsyncFunction = Arc<dyn Fn(Vec<Value>) -> BoxFuture + Send + Sync>;
 
-pub enum Value<'a> {
-    Int(u32),
+/// A function that depends on the language of the render, aka: ordinals or messages. See `Call`
+pub type LocalizedFunction =
+    Arc<dyn Fn(Vec<Value>, &mut Call) -> Result<Option<Value>, RuntimeError> + Send + Sync>;
+
+#[derive(Clone)]
+pub enum Value {
+    /// Whole numbers. Integers over `i64::MAX` (from a `u64` or serde) become a `Float`,
+    /// which loses precision past 2^53.
+    Int(i64),
+    Float(f64),
     /// Should return any value but a function (or object for now--lazy to implement atm), because functions aren't supported in the langauge
-    Function(&'a dyn Fn(Vec<Value<'_>>) -> Option<Value<'_>>), // NOTE: May chage it to a Result<> with a error message with more infor
+    Function(Function), // NOTE: May chage it to a Result<> with a error message with more infor
+    AsyncFunction(AsyncFunction),
+    /// A Rust function with a signature, see `IntoFunction`
+    Typed(TypedFunction),
+    Localized(LocalizedFunction),
     String(String),
     Object(ObjectHashMap),
+    /// An object whose properties are only resolved when a template references them
+    Lazy(Arc<dyn Resolver>),
+    Bool(bool),
+    List(Vec<Value>),
+    /// No value, aka: an unset nickname. Renders as `ValueFormat::null`
+    Null,
+    Date(Timestamp),
+}
+
+impl Value {
+    pub fn function<F>(func: F) -> Self
+    where
+        F: Fn(Vec<Value>) -> Option<Value> + Send + Sync + 'static,
+    {
+        Value::Function(Arc::new(func))
+    }
+
+    pub fn async_function<F, Fut>(func: F) -> Self
+    where
+        F: Fn(Vec<Value>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Option<Value>> + Send + 'static,
+    {
+        Value::AsyncFunction(Arc::new(move |args| Box::pin(func(args))))
+    }
+
+    pub fn localized<F>(func: F) -> Self
+    where
+        F: Fn(Vec<Value>, &mut Call) -> Result<Option<Value>, RuntimeError> + Send + Sync + 'static,
+    {
+        Value::Localized(Arc::new(func))
+    }
+
+    /// Aka: `Value::typed_function(|n: i64| n * 2)`
+    pub fn typed_function<Args, F: IntoFunction<Args>>(func: F) -> Self {
+        Value::Typed(func.into_function())
+    }
+
+    pub fn lazy<R: Resolver + 'static>(resolver: R) -> Self {
+        Value::Lazy(Arc::new(resolver))
+    }
+
+    /// Name of the type of value, used in errors
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Value::Int(_) => "Int",
+            Value::Float(_) => "Float",
+            Value::Function(_) | Value::Typed(_) | Value::Localized(_) => "Function",
+            Value::AsyncFunction(_) => "AsyncFunction",
+            Value::String(_) => "String",
+            Value::Object(_) | Value::Lazy(_) => "Object",
+            Value::Bool(_) => "Bool",
+            Value::List(_) => "List",
+            Value::Null => "Null",
+            Value::Date(_) => "Date",
+        }
+    }
+
+    /// The text written to the output for this value, `None` for objects and functions
+    /// (or lists containing them) which have no text form.
+    pub fn render(&self, format: &ValueFormat) -> Option<String> {
+        let mut text = String::new();
+        self.render_to(&mut text, format)?.ok()?;
+        Some(text)
+    }
+
+    /// Same as `render`, writing to `out` instead of allocating a new `String`
+    pub fn render_to<W: fmt::Write + ?Sized>(
+        &self,
+        out: &mut W,
+        format: &ValueFormat,
+    ) -> Option<fmt::Result> {
+        // Checked beforehand so nothing of an unrenderable list gets written
+        if !self.is_renderable() {
+            return None;
+        }
+        Some(self.write_text(out, format))
+    }
+
+    fn is_renderable(&self) -> bool {
+        match self {
+            Value::Function(_)
+            | Value::AsyncFunction(_)
+            | Value::Typed(_)
+            | Value::Localized(_)
+            | Value::Object(_)
+            | Value::Lazy(_) => false,
+            Value::List(items) => items.iter().all(Value::is_renderable),
+            _ => true,
+        }
+    }
+
+    fn write_text<W: fmt::Write + ?Sized>(&self, out: &mut W, format: &ValueFormat) -> fmt::Result {
+        match self {
+            Value::Int(int) => write!(out, "{}", int),
+            Value::Float(float) => write!(out, "{}", float),
+            Value::String(string) => out.write_str(string),
+            Value::Bool(bool) => write!(out, "{}", bool),
+            Value::Null => out.write_str(&format.null),
+            Value::Date(timestamp) => write!(out, "{}", timestamp),
+            Value::List(items) => {
+                for (index, item) in items.iter().enumerate() {
+                    if index > 0 {
+                        out.write_str(format.separator_before(index, items.len()))?;
+                    }
+                    item.write_text(out, format)?;
+                }
+                Ok(())
+            }
+            Value::Function(_)
+            | Value::AsyncFunction(_)
+            | Value::Typed(_)
+            | Value::Localized(_)
+            | Value::Object(_)
+            | Value::Lazy(_) => Ok(()),
+        }
+    }
+}
+
+/// Functions have nothing to show but their kind, objects are sorted by key so the output is stable
+impl fmt::Debug for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Int(int) => f.debug_tuple("Int").field(int).finish(),
+            Value::Float(float) => f.debug_tuple("Float").field(float).finish(),
+            Value::Function(_) => write!(f, "Function"),
+            Value::AsyncFunction(_) => write!(f, "AsyncFunction"),
+            Value::Typed(func) => write!(f, "Function{}", func.signature()),
+            Value::Localized(_) => write!(f, "Localized"),
+            Value::String(string) => f.debug_tuple("String").field(string).finish(),
+            Value::Object(object) => {
+                let mut entries: Vec<_> = object.iter().collect();
+                entries.sort_by_key(|(key, _)| *key);
+                f.debug_map().entries(entries).finish()
+            }
+            Value::Lazy(_) => write!(f, "Lazy"),
+            Value::Bool(bool) => f.debug_tuple("Bool").field(bool).finish(),
+            Value::List(items) => f.debug_list().entries(items).finish(),
+            Value::Null => write!(f, "Null"),
+            Value::Date(timestamp) => f.debug_tuple("Date").field(&timestamp.unix()).finish(),
+        }
+    }
+}
+
+/// How values without a single obvious text form are written to the output
+#[derive(Debug, Clone)]
+pub struct ValueFormat {
+    /// Between the items of a list, `", "` by default
+    pub list_separator: String,
+    /// Between the last two items of a list when set, aka: `" and "` for "a, b and c"
+    pub list_last_separator: Option<String>,
+    /// What `Value::Null` renders as, nothing by default
+    pub null: String,
 }
 
+impl Default for ValueFormat {
+    fn default() -> Self {
+        ValueFormat {
+            list_separator: ", ".to_string(),
+            list_last_separator: None,
+            null: String::new(),
+        }
+    }
+}
+
+impl ValueFormat {
+    fn separator_before(&self, index: usize, len: usize) -> &str {
+        match &self.list_last_separator {
+            Some(last_separator) if index + 1 == len => last_separator,
+            _ => &self.list_separator,
+        }
+    }
+}
+
+macro_rules! value_from_int {
+    ($($int:ty),*) => {
+        $(
+            impl From<$int> for Value {
+                fn from(int: $int) -> Self {
+                    Value::Int(int.into())
+                }
+            }
+        )*
+    };
+}
+
+value_from_int!(u8, u16, u32, i8, i16, i32, i64);
+
+/// Integers that don't fit in an `Int` (over `i64::MAX`) become a `Float`
+macro_rules! value_from_wide_int {
+    ($($int:ty),*) => {
+        $(
+            impl From<$int> for Value {
+                fn from(int: $int) -> Self {
+                    match i64::try_from(int) {
+                        Ok(int) => Value::Int(int),
+                        Err(_) => Value::Float(int as f64),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+value_from_wide_int!(u64, usize);
+
+impl From<f32> for Value {
+    fn from(float: f32) -> Self {
+        Value::Float(float.into())
+    }
+}
+
+impl From<f64> for Value {
+    fn from(float: f64) -> Self {
+        Value::Float(float)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(bool: bool) -> Self {
+        Value::Bool(bool)
+    }
+}
+
+impl From<String> for Value {
+    fn from(string: String) -> Self {
+        Value::String(string)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(string: &str) -> Self {
+        Value::String(string.to_string())
+    }
+}
+
+impl From<Timestamp> for Value {
+    fn from(timestamp: Timestamp) -> Self {
+        Value::Date(timestamp)
+    }
+}
+
+impl From<SystemTime> for Value {
+    fn from(time: SystemTime) -> Self {
+        Value::Date(time.into())
+    }
+}
+
+impl From<ObjectHashMap> for Value {
+    fn from(object: ObjectHashMap) -> Self {
+        Value::Object(object)
+    }
+}
+
+impl<T: Into<Value>> From<Vec<T>> for Value {
+    fn from(items: Vec<T>) -> Self {
+        Value::List(items.into_iter().map(Into::into).collect())
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(option: Option<T>) -> Self {
+        option.map_or(Value::Null, Into::into)
+    }
+}
+
+#[derive(Default)]
 pub struct Context(pub ObjectHashMap);
 
 impl Context {
@@ -23,181 +326,700 @@ impl Context {
     pub fn add_object(&mut self, name: String, object: ObjectHashMap) {
         self.0.insert(name, Value::Object(object));
     }
-    pub fn get(&self, key: &str) -> Option<&Value<'_>> {
+    pub fn get(&self, key: &str) -> Option<&Value> {
         self.0.get(key)
     }
+    /// Adds a Rust function, its arguments checked against its signature. See `IntoFunction`
+    pub fn add_function<Args>(&mut self, name: String, func: impl IntoFunction<Args>) {
+        self.0.insert(name, Value::typed_function(func));
+    }
+}
+
+/// What a tag renders as when its value is not in the context
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum UndefinedMode {
+    /// Fail the render with the error
+    #[default]
+    Strict,
+    /// Render nothing in place of the tag
+    Empty,
+    /// Render the tag as it was written, aka: "{user.foo}"
+    KeepTag,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RenderOptions {
+    /// Anything but `Strict` records the undefined values in `Rendered::warnings` instead of failing
+    pub undefined: UndefinedMode,
+    /// How lists, nulls... are written to the output
+    pub format: ValueFormat,
+    /// Applied to the values of tags, not the text around them
+    pub escape: Escape,
+    pub limits: Limits,
+    /// Language of the localized functions, and their messages. English by default
+    pub locale: Arc<Locale>,
+}
+
+/// Bounds on a render, for templates written by untrusted users. Unlimited by default.
+///
+/// The language has no loops, but messages of the locale can include other messages. Each include
+/// renders the whole message again, so a few levels of them fanning out is enough to do a lot of
+/// work from a short template: bound them with `max_message_depth`, `max_function_calls` and
+/// `max_output_bytes`.
+#[derive(Debug, Clone, Default)]
+pub struct Limits {
+    /// Aka: 2000 for a Discord message, which is 2000 characters of ASCII.
+    /// Messages count toward it as they are rendered, not only once written.
+    pub max_output_bytes: Option<usize>,
+    pub max_function_calls: Option<usize>,
+    /// How many messages can be rendered inside one another, aka: 1 allows messages that include none
+    pub max_message_depth: Option<usize>,
+    /// Checked between the tags and text of the template, a function that hangs is not interrupted
+    pub timeout: Option<Duration>,
+}
+
+/// Renders templates against `global`, a `Context` or any other `Resolver` (aka: a lazy one)
+pub struct Runtime<R: Resolver = Context> {
+    pub global: R,
+    pub options: RenderOptions,
+}
+
+impl<R: Resolver> Runtime<R> {
+    pub fn new(context: R) -> Self {
+        Runtime {
+            global: context,
+            options: RenderOptions::default(),
+        }
+    }
+
+    /// Lexes and parses `program` once, the template can then be rendered any number of times.
+    ///
+    /// Returns every syntax error of the template when it is not valid.
+    pub fn compile(&self, program: &str) -> Result<Template, Vec<RuntimeError>> {
+        Template::compile(program)
+    }
+
+    /// Renders a compiled template against the global context
+    pub fn render(&self, template: &Template) -> Result<String, RuntimeError> {
+        Ok(self.render_with_warnings(template)?.output)
+    }
+
+    /// Same as `render`, with what was glossed over when rendering with a lenient `UndefinedMode`
+    pub fn render_with_warnings(&self, template: &Template) -> Result<Rendered, RuntimeError> {
+        template.render_with(&self.global, &self.options)
+    }
+
+    /// Writes the render straight to `out`, see `Template::render_to`
+    pub fn render_to<W: fmt::Write>(
+        &self,
+        template: &Template,
+        out: &mut W,
+    ) -> Result<Vec<RuntimeError>, RuntimeError> {
+        template.render_to(out, &self.global, &self.options)
+    }
+
+    /// Writes the render straight to `out`, see `Template::write_to`
+    pub fn write_to<W: io::Write>(
+        &self,
+        template: &Template,
+        out: &mut W,
+    ) -> Result<Vec<RuntimeError>, RuntimeError> {
+        template.write_to(out, &self.global, &self.options)
+    }
+
+    /// Renders a compiled template, awaiting its async functions.
+    ///
+    /// Every call in the template is independent of the others, so they are all polled at once.
+    pub async fn render_async(&self, template: &Template) -> Result<String, RuntimeError> {
+        Ok(template
+            .render_async_with(&self.global, &self.options)
+            .await?
+            .output)
+    }
+
+    /// Renders with the part of the template each part of the output comes from, see `Template::render_traced`
+    pub fn render_traced(&self, template: &Template) -> Result<Traced, RuntimeError> {
+        template.render_traced(&self.global, &self.options)
+    }
+
+    pub fn execute(&self, program: &str) -> Result<String, RuntimeError> {
+        let template = self
+            .compile(program)
+            .map_err(|mut errors| errors.remove(0))?;
+        self.render(&template)
+    }
+}
+
+/// What a render keeps track of as it goes
+pub(crate) struct RenderState {
+    pub(crate) warnings: Vec<RuntimeError>,
+    calls: usize,
+    deadline: Option<Instant>,
+    /// Keys of the messages being rendered, the innermost last
+    messages: Vec<String>,
+    /// Bytes the output can still take, which a message rendered by the current tag is held to
+    output_left: Option<usize>,
+    /// Segments of a traced render, `None` when not tracing
+    pub(crate) trace: Option<Vec<Segment>>,
+    /// What the tag being rendered resolved to, taken by `process_body`
+    traced: Option<(SegmentKind, Option<String>)>,
+}
+
+impl RenderState {
+    /// Keeps what a tag resolved to, only formatted when tracing
+    fn trace_tag(&mut self, kind: SegmentKind, value: Option<&Value>) {
+        if self.trace.is_some() {
+            self.traced = Some((kind, value.map(|value| format!("{:?}", value))));
+        }
+    }
+}
+
+/// What a `Value::Localized` function is called with, besides its arguments
+pub struct Call<'r, 'c> {
+    renderer: &'r Renderer<'c>,
+    state: &'r mut RenderState,
+    expression: &'r Expression,
+    /// Set once a message is rendered, the result is then written without escaping it again
+    escaped: bool,
 }
 
-pub struct Runtime {
-    pub global: Context,
+impl<'c> Call<'_, 'c> {
+    pub fn locale(&self) -> &'c Locale {
+        &self.renderer.options.locale
+    }
+
+    /// Renders the message `key` of the locale against the context of the render, counting
+    /// toward its limits.
+    ///
+    /// Warnings of a lenient render point into the message, not the template.
+    ///
+    /// Tags of the message are escaped as the render wants, not its text. So the result of the call
+    /// is written as it is, which also goes for anything else the function returns with it.
+    pub fn message(&mut self, key: &str) -> Result<String, RuntimeError> {
+        let error = |message: String| RuntimeError::Message {
+            key: key.to_string(),
+            message,
+            start: self.expression.start,
+            end: self.expression.end,
+        };
+        let locale = self.locale();
+        let Some(template) = locale.message(key) else {
+            return Err(error(format!("Not in the catalog of '{}'", locale.tag)));
+        };
+        if self.state.messages.iter().any(|message| message == key) {
+            return Err(error("Includes itself".to_string()));
+        }
+        let (start, end) = (self.expression.start, self.expression.end);
+        let limits = &self.renderer.options.limits;
+        if let Some(limit) = limits.max_message_depth {
+            if self.state.messages.len() >= limit {
+                return Err(RuntimeError::MessageDepth { limit, start, end });
+            }
+        }
+
+        let renderer = Renderer {
+            context: self.renderer.context,
+            options: self.renderer.options,
+            source: template.source(),
+        };
+        let mut text = String::new();
+        let output_left = self.state.output_left;
+        self.state.messages.push(key.to_string());
+        // The message is one segment of the template, its own tags aren't traced
+        let trace = self.state.trace.take();
+        let result = renderer.process_body(
+            &template.ast().body,
+            &mut Output::new(&mut text, output_left),
+            self.state,
+        );
+        self.state.trace = trace;
+        self.state.messages.pop();
+        // What the message wrote is only written to the output once the call returns
+        self.state.output_left = output_left.map(|left| left.saturating_sub(text.len()));
+
+        match result {
+            Ok(()) => {
+                self.escaped = true;
+                Ok(text)
+            }
+            // Limits are of the whole render
+            Err(RuntimeError::CallLimit { limit, .. }) => {
+                Err(RuntimeError::CallLimit { limit, start, end })
+            }
+            Err(RuntimeError::OutputLimit { limit, .. }) => Err(RuntimeError::OutputLimit {
+                limit: limits.max_output_bytes.unwrap_or(limit),
+                start,
+                end,
+            }),
+            Err(RuntimeError::MessageDepth { limit, .. }) => {
+                Err(RuntimeError::MessageDepth { limit, start, end })
+            }
+            Err(RuntimeError::Timeout { limit, .. }) => {
+                Err(RuntimeError::Timeout { limit, start, end })
+            }
+            // Already about the message that failed
+            Err(err @ RuntimeError::Message { .. }) => Err(err),
+            Err(err) => Err(error(err.to_string())),
+        }
+    }
 }
 
-impl Runtime {
-    pub fn new(context: Context) -> Self {
-        Runtime { global: context }
+/// Why writing to `out` failed, it going over the output limit or the writer itself
+fn write_error<W: fmt::Write>(out: &Output<W>, start: PPosition, end: PPosition) -> RuntimeError {
+    match out.limit() {
+        Some(limit) if out.over_limit => RuntimeError::OutputLimit { limit, start, end },
+        _ => RuntimeError::Write {
+            message: "Failed to write the output".to_string(),
+            start,
+            end,
+        },
     }
+}
 
-    pub fn execute(&self, program: &str) -> Result<String, String> {
-        let ast = self.parse_ast(program);
-        let buffer = self.process_body(ast.body);
+fn stat_position(stat: &Stat) -> (PPosition, PPosition) {
+    match stat {
+        Stat::Tag { start, end, .. } | Stat::Text { start, end, .. } => (*start, *end),
+        Stat::Error(node) => (node.start, node.end),
+    }
+}
 
-        Ok(buffer.join(""))
+/// Bytes of the source the stat was parsed from
+fn stat_span(stat: &Stat) -> Span {
+    match stat {
+        Stat::Tag { span, .. } | Stat::Text { span, .. } => *span,
+        Stat::Error(node) => node.span,
     }
+}
 
-    fn process_body(&self, body: Vec<Stat>) -> Vec<String> {
-        let mut buffer: Vec<String> = Vec::new();
+/// Evaluates a template's AST against a context
+pub(crate) struct Renderer<'c> {
+    pub(crate) context: &'c dyn Resolver,
+    pub(crate) options: &'c RenderOptions,
+    /// Source of the template, for `UndefinedMode::KeepTag`
+    pub(crate) source: &'c str,
+}
 
+impl<'c> Renderer<'c> {
+    pub(crate) fn state(&self) -> RenderState {
+        RenderState {
+            warnings: Vec::new(),
+            calls: 0,
+            deadline: self
+                .options
+                .limits
+                .timeout
+                .map(|timeout| Instant::now() + timeout),
+            messages: Vec::new(),
+            output_left: None,
+            trace: None,
+            traced: None,
+        }
+    }
+
+    /// Writes the body straight to `out`, without buffering it
+    pub(crate) fn process_body<W: fmt::Write>(
+        &self,
+        body: &[Stat],
+        out: &mut Output<W>,
+        state: &mut RenderState,
+    ) -> Result<(), RuntimeError> {
         for stat in body {
-            match stat {
+            let (start, end) = stat_position(stat);
+            self.check_deadline(state, start, end)?;
+
+            let from = out.written();
+            state.output_left = out.limit().map(|limit| limit.saturating_sub(from));
+            let written = match stat {
                 Stat::Tag {
-                    _type,
-                    start: _,
-                    end: _,
-                    value,
-                } => {
-                    self.process_expression(value, &mut buffer);
-                }
-                Stat::Text {
-                    _type,
-                    value,
-                    start: _,
-                    end: _,
-                } => buffer.push(value),
+                    _type, span, value, ..
+                } => match self.process_expression(value, out, state) {
+                    Err(err) => {
+                        let fallback = self.undefined_fallback(err, *span, state)?;
+                        out.write_str(fallback)
+                    }
+                    Ok(written) => written,
+                },
+                Stat::Text { _type, value, .. } => out.write_str(value),
+                // Only in templates with syntax errors, which never compile
+                Stat::Error(_) => continue,
+            };
+            written.map_err(|_| write_error(out, start, end))?;
+
+            // A tag that resolved to nothing was replaced by its fallback
+            let (kind, value) = match stat {
+                Stat::Tag { .. } => state
+                    .traced
+                    .take()
+                    .unwrap_or((SegmentKind::Undefined, None)),
+                _ => (SegmentKind::Text, None),
             };
+            if let Some(segments) = &mut state.trace {
+                let span = stat_span(stat);
+                segments.push(Segment {
+                    output: from..out.written(),
+                    source: span.start..span.end,
+                    kind,
+                    value,
+                });
+            }
         }
 
-        buffer
+        Ok(())
     }
 
-    fn process_expression(&self, expression: Expression, buffer: &mut Vec<String>) {
-        // NOTE: Should not be None, but can be, because of the parser error-recovery
-        let vproperty = expression.property.unwrap();
-        if let PValue::Property(property) = vproperty {
-            match self.property_type_find_value(property.value) {
-                Value::String(s) => {
-                    buffer.push(s.to_string());
-                    // println!("{}", s)
-                }
-                // NOTE: P return Function or Obropery function can NOTject (argument can return object but not function)
-                Value::Function(func) => {
-                    let args = self.collect_arguments1(expression.arguments);
-                    let fn_results_pre = func(args);
-
-                    if fn_results_pre.is_none() {
-                        buffer.push("(NONE)".to_string());
-                    } else {
-                        let fn_results = fn_results_pre.unwrap();
-                        match fn_results {
-                            Value::Int(int) => buffer.push(int.to_string()),
-                            Value::String(st) => buffer.push(st),
-                            Value::Object(_) => panic!("Unsupported behaver"),
-                            Value::Function(_) => panic!("Unexpected behaver"),
-                        }
+    /// Same as `process_body`, except async functions are called and their futures joined.
+    ///
+    /// The output is only written once every call is done, since they finish in any order.
+    pub(crate) async fn process_body_async<W: fmt::Write>(
+        &self,
+        body: &[Stat],
+        out: &mut Output<'_, W>,
+        state: &mut RenderState,
+    ) -> Result<(), RuntimeError> {
+        // Text of each part of the body, calls are filled in once all of them are done
+        let mut buffer: Vec<(String, PPosition, PPosition)> = Vec::new();
+        let mut slots: Vec<(usize, &Expression)> = Vec::new();
+        let mut calls: Vec<BoxFuture> = Vec::new();
+        let mut buffered = out.written();
+
+        for stat in body {
+            let (start, end) = stat_position(stat);
+            self.check_deadline(state, start, end)?;
+
+            let mut text = String::new();
+            state.output_left = out.limit().map(|limit| limit.saturating_sub(buffered));
+            match stat {
+                Stat::Tag { value, .. } => {
+                    // Resolved once, whether it turns out to be an async function or not
+                    let resolved = match &value.property {
+                        PValue::Property(property) => self
+                            .property_type_find_value(property)
+                            .map(|resolved| Some((property, resolved))),
+                        _ => Ok(None),
+                    };
+                    let result = match resolved {
+                        Ok(Some((property, resolved))) => match &*resolved {
+                            Value::AsyncFunction(func) => {
+                                self.async_call(func, value, state).map(|call| {
+                                    slots.push((buffer.len(), value));
+                                    calls.push(call);
+                                    Ok(())
+                                })
+                            }
+                            resolved => self.process_value(
+                                value,
+                                property,
+                                resolved,
+                                &mut Output::new(&mut text, None),
+                                state,
+                            ),
+                        },
+                        // Recovered from a syntax error, templates with one never get rendered
+                        Ok(None) => Ok(Ok(())),
+                        Err(err) => Err(err),
+                    };
+                    if let Err(err) = result {
+                        text = self
+                            .undefined_fallback(err, stat_span(stat), state)?
+                            .to_string();
                     }
                 }
-                _ => panic!("Unsupported property value"),
-            }
+                Stat::Text { value, .. } => text.push_str(value),
+                Stat::Error(_) => continue,
+            };
+            buffered += text.len();
+            buffer.push((text, start, end));
         }
-        // others should be taken care by the parser, TODO: add support for others.. manybe
-    }
 
-    fn property_type_find_value(&self, stack: Vec<String>) -> &Value<'_> {
-        let mut travarsed: Option<&Value<'_>> = None;
+        // NOTE: A call that never completes is not interrupted, that's up to the executor (aka: tokio::time::timeout)
+        let results = JoinAll::new(calls).await;
+        for ((index, expression), fn_results) in slots.into_iter().zip(results) {
+            self.check_deadline(state, expression.start, expression.end)?;
+            let mut text = String::new();
+            // Writing to a String can't fail
+            let _ = self.function_result(fn_results, expression, expression.raw, &mut text)?;
+            buffer[index].0 = text;
+        }
 
-        for st in stack {
-            if travarsed.is_none() {
-                travarsed = self.global.get(&st);
-                continue;
-            }
+        for (text, start, end) in buffer {
+            out.write_str(&text)
+                .map_err(|_| write_error(out, start, end))?;
+        }
 
-            // NOTE: Not 100% sure what direction to take language at the moment. The language is more function-oriented so far
-            // If I decided to make it object-oriented then String and Int should be traversable.
-            // But at the moment I'm stiking with function oriented as the future, also seems easier (right now) to develop.
+        Ok(())
+    }
 
-            if let Value::Object(obj) = travarsed.unwrap() {
-                travarsed = obj.get(&st);
-                continue;
-            } else if let Value::String(_) = travarsed.unwrap() {
-                panic!("Can't tervarse a string")
-            } else if let Value::Int(_) = travarsed.unwrap() {
-                panic!("Can't tervarse a Int")
-            } else if let Value::Function(_) = travarsed.unwrap() {
-                panic!("Can't tervarse a function")
+    fn check_deadline(
+        &self,
+        state: &RenderState,
+        start: PPosition,
+        end: PPosition,
+    ) -> Result<(), RuntimeError> {
+        match (state.deadline, self.options.limits.timeout) {
+            (Some(deadline), Some(limit)) if Instant::now() >= deadline => {
+                Err(RuntimeError::Timeout { limit, start, end })
             }
-            // match tervarsed.unwrap() {}
+            _ => Ok(()),
         }
-
-        travarsed.unwrap()
     }
 
-    fn collect_arguments1(&self, arguments_pre: Option<Vec<Arg>>) -> Vec<Value<'_>> {
-        let mut args = Vec::new();
+    fn count_call(
+        &self,
+        state: &mut RenderState,
+        expression: &Expression,
+    ) -> Result<(), RuntimeError> {
+        state.calls += 1;
+        match self.options.limits.max_function_calls {
+            Some(limit) if state.calls > limit => Err(RuntimeError::CallLimit {
+                limit,
+                start: expression.start,
+                end: expression.end,
+            }),
+            _ => Ok(()),
+        }
+    }
 
-        if arguments_pre.is_none() {
-            return args;
+    /// What a tag that failed to render is replaced with, when the error is about an undefined
+    /// value and the render is lenient. The error is then kept as a warning.
+    fn undefined_fallback(
+        &self,
+        err: RuntimeError,
+        span: Span,
+        state: &mut RenderState,
+    ) -> Result<&'c str, RuntimeError> {
+        if !matches!(
+            err,
+            RuntimeError::UndefinedProperty { .. } | RuntimeError::NotTraversable { .. }
+        ) {
+            return Err(err);
         }
-        let arguments = arguments_pre.unwrap();
 
-        for arg in arguments {
-            // NOTE: At the moment only single arguments are supported...
-            let Arg::Single(data) = arg;
-            match data.value {
-                PValue::String {
-                    _type,
-                    start: _,
-                    end: _,
-                    value,
-                } => args.push(Value::String(value)),
-                _ => todo!(),
-            }
+        let fallback = match self.options.undefined {
+            UndefinedMode::Strict => return Err(err),
+            UndefinedMode::Empty => "",
+            UndefinedMode::KeepTag => &self.source[span.start..span.end],
+        };
+        state.warnings.push(err);
+
+        Ok(fallback)
+    }
+
+    /// Starts the call of a tag that is an async function
+    fn async_call(
+        &self,
+        func: &AsyncFunction,
+        expression: &Expression,
+        state: &mut RenderState,
+    ) -> Result<BoxFuture, RuntimeError> {
+        let args = self.collect_arguments1(&expression.arguments)?;
+        self.count_call(state, expression)?;
+
+        Ok(func(args))
+    }
+
+    /// `raw` when the result is not to be escaped, aka: "{!fn}" or a rendered message
+    fn function_result<W: fmt::Write>(
+        &self,
+        fn_results_pre: Option<Value>,
+        expression: &Expression,
+        raw: bool,
+        out: &mut W,
+    ) -> Result<fmt::Result, RuntimeError> {
+        let Some(fn_results) = fn_results_pre else {
+            return Ok(out.write_str("(NONE)"));
+        };
+
+        self.write_value(&fn_results, raw, expression.start, expression.end, out)
+    }
+
+    /// Writes the value escaped, as the options and the tag want it
+    ///
+    /// The outer `Result` is the value not being renderable, the inner one the output failing.
+    fn write_value<W: fmt::Write>(
+        &self,
+        value: &Value,
+        raw: bool,
+        start: PPosition,
+        end: PPosition,
+        out: &mut W,
+    ) -> Result<fmt::Result, RuntimeError> {
+        let unrenderable = || RuntimeError::Unrenderable {
+            kind: value.kind(),
+            start,
+            end,
+        };
+
+        match &self.options.escape {
+            Escape::None => value.render_to(out, &self.options.format),
+            _ if raw => value.render_to(out, &self.options.format),
+            escape => value
+                .render(&self.options.format)
+                .map(|text| out.write_str(&escape.escape(&text))),
         }
+        .ok_or_else(unrenderable)
+    }
+
+    fn process_expression<W: fmt::Write>(
+        &self,
+        expression: &Expression,
+        out: &mut W,
+        state: &mut RenderState,
+    ) -> Result<fmt::Result, RuntimeError> {
+        // NOTE: 'Missing' or 'Error' come from the parser error-recovery, which stops before running
+        let PValue::Property(property) = &expression.property else {
+            // others should be taken care by the parser, TODO: add support for others.. manybe
+            return Ok(Ok(()));
+        };
+        let value = self.property_type_find_value(property)?;
 
-        args
+        self.process_value(expression, property, &value, out, state)
     }
 
-    fn parse_ast(&self, source: &str) -> Source {
-        let mut tokenizer = Lexer::from_source(source);
-        let tokens_res = tokenizer.scan_tokens();
-        if tokens_res.is_err() {
-            panic!(
-                "\n{}{}\nAt {:?}\n",
-                source,
-                format!(
-                    "{}^ {}",
-                    left_pad(tokenizer.pointer - 1, None),
-                    tokens_res.err().unwrap()
-                ),
-                tokenizer.position
-            )
+    /// Writes what the property of the tag resolved to, calling it when it's a function
+    fn process_value<W: fmt::Write>(
+        &self,
+        expression: &Expression,
+        property: &Property,
+        value: &Value,
+        out: &mut W,
+        state: &mut RenderState,
+    ) -> Result<fmt::Result, RuntimeError> {
+        match value {
+            // NOTE: P return Function or Obropery function can NOTject (argument can return object but not function)
+            Value::Function(func) => {
+                let args = self.collect_arguments1(&expression.arguments)?;
+                self.count_call(state, expression)?;
+                let fn_results_pre = func(args);
+                state.trace_tag(SegmentKind::Call, fn_results_pre.as_ref());
+
+                self.function_result(fn_results_pre, expression, expression.raw, out)
+            }
+            Value::Typed(func) => {
+                let args = self.collect_arguments1(&expression.arguments)?;
+                self.count_call(state, expression)?;
+                let result = func.call(args).map_err(|message| RuntimeError::Arguments {
+                    function: property.value.join("."),
+                    message,
+                    start: expression.start,
+                    end: expression.end,
+                })?;
+                state.trace_tag(SegmentKind::Call, Some(&result));
+
+                self.write_value(
+                    &result,
+                    expression.raw,
+                    expression.start,
+                    expression.end,
+                    out,
+                )
+            }
+            Value::Localized(func) => {
+                let args = self.collect_arguments1(&expression.arguments)?;
+                self.count_call(state, expression)?;
+                let mut call = Call {
+                    renderer: self,
+                    state,
+                    expression,
+                    escaped: false,
+                };
+                let fn_results_pre = func(args, &mut call)?;
+                let raw = expression.raw || call.escaped;
+                state.trace_tag(SegmentKind::Call, fn_results_pre.as_ref());
+
+                self.function_result(fn_results_pre, expression, raw, out)
+            }
+            Value::AsyncFunction(_) => Err(RuntimeError::AsyncFunction {
+                start: property.start,
+                end: property.end,
+            }),
+            value => {
+                state.trace_tag(SegmentKind::Value, Some(value));
+                self.write_value(value, expression.raw, property.start, property.end, out)
+            }
         }
+    }
+
+    /// Borrowed from the context when it can be, lazy objects hand out owned values
+    fn property_type_find_value(
+        &self,
+        property: &Property,
+    ) -> Result<Cow<'c, Value>, RuntimeError> {
+        let stack = &property.value;
+        let path = |depth: usize| stack.iter().take(depth + 1).cloned().collect();
+        let undefined = |depth: usize| RuntimeError::UndefinedProperty {
+            path: path(depth),
+            start: property.start,
+            end: property.end,
+        };
 
-        let mut parser = Parser::from_lexer(tokenizer);
-        let parse_results = parser.parse();
+        let mut travarsed: Cow<'c, Value> = stack
+            .first()
+            .and_then(|st| self.context.lookup(st))
+            .ok_or_else(|| undefined(0))?;
 
-        if parse_results.errors.len() > 0 {
-            let error = &parse_results.errors[0];
-            panic!(
-                "\n{}{}\n",
-                source,
-                format!(
-                    "{}{} {}",
-                    left_pad(error.start.line, None),
-                    left_pad(error.end.line - error.start.line, Some("^")),
-                    error.message
-                ),
-            )
+        for (depth, st) in stack.iter().enumerate().skip(1) {
+            // NOTE: Anything but an object is traversed through its built-in properties, aka: "name.length"
+            let next = match travarsed {
+                Cow::Borrowed(Value::Object(obj)) => obj.get(st).map(Cow::Borrowed),
+                Cow::Owned(Value::Object(mut obj)) => obj.remove(st).map(Cow::Owned),
+                Cow::Borrowed(Value::Lazy(resolver)) => resolver.lookup(st),
+                Cow::Owned(Value::Lazy(resolver)) => resolver.get(st).map(Cow::Owned),
+                _ => match properties::get(&travarsed, st, &self.options.locale) {
+                    Some(value) => Some(Cow::Owned(value)),
+                    None => {
+                        return Err(RuntimeError::NotTraversable {
+                            path: path(depth),
+                            kind: travarsed.kind(),
+                            start: property.start,
+                            end: property.end,
+                        })
+                    }
+                },
+            };
+            travarsed = next.ok_or_else(|| undefined(depth))?;
         }
 
-        parse_results.ast
+        Ok(travarsed)
     }
-}
 
-fn left_pad(p: usize, char: Option<&str>) -> String {
-    let mut res = "".to_string();
-    for _ in 0..p {
-        res += if char.is_none() { " " } else { char.unwrap() }
+    fn collect_arguments1(
+        &self,
+        arguments_pre: &Option<Vec<Arg>>,
+    ) -> Result<Vec<Value>, RuntimeError> {
+        let mut args = Vec::new();
+
+        let Some(arguments) = arguments_pre else {
+            return Ok(args);
+        };
+
+        for arg in arguments {
+            let data = match arg {
+                Arg::Single(data) => data,
+                // Recovered from a syntax error, templates with one never get rendered
+                Arg::Error(node) => {
+                    return Err(RuntimeError::UnsupportedArgument {
+                        start: node.start,
+                        end: node.end,
+                    })
+                }
+            };
+            let value = match &data.value {
+                PValue::String { value, .. } => Value::String(value.clone()),
+                PValue::Int { value, .. } => Value::Int(i64::from(*value)),
+                // Objects (and functions) are passed as they are, the function decides what to do with them
+                PValue::Property(property) => self.property_type_find_value(property)?.into_owned(),
+                PValue::Missing { .. } | PValue::Error(_) => {
+                    return Err(RuntimeError::UnsupportedArgument {
+                        start: data.start,
+                        end: data.end,
+                    })
+                }
+            };
+            args.push(value);
+        }
+
+        Ok(args)
     }
-    res
 }
 
 #[cfg(test)]
@@ -210,14 +1032,17 @@ mod tests {
         // let program = "Hello, welcome to \n {guild.name} {guild.saymore | \"World\"}\n";
         let mut context = Context::new();
 
+        // Captured by the function, like a database pool or per-guild config would be
+        let suffix = "_sayingmore".to_string();
+
         let mut guild_object: ObjectHashMap = HashMap::new();
         guild_object.insert("name".to_string(), Value::String("BarFight".to_string()));
         guild_object.insert(
             "saymore".to_string(),
-            Value::Function(&|args| {
+            Value::function(move |args| {
                 if args.len() == 1 {
                     if let Value::String(msg) = &args[0] {
-                        return Some(Value::String(format!("{}_sayingmore", msg)));
+                        return Some(Value::String(format!("{}{}", msg, suffix)));
                     }
                     return None;
                 }
@@ -235,7 +1060,9 @@ mod tests {
 
         let runtime = Runtime::new(context);
 
-        println!("{:?}", runtime.execute(program))
+        let results = runtime.execute(program);
+        println!("{:?}", results);
+        assert!(results.unwrap().contains("world_sayingmore"));
 
         // Value => Int | String | Object | Function
         // Object(HashMap<String, Value>)
@@ -246,4 +1073,332 @@ mod tests {
         //     "".to_string()
         // };
     }
+
+    #[test]
+    fn runtime_errors() {
+        let mut context = Context::new();
+        let mut guild_object: ObjectHashMap = HashMap::new();
+        guild_object.insert("name".to_string(), Value::String("BarFight".to_string()));
+        guild_object.insert("count".to_string(), Value::Int(7));
+        context.add_object("guild".to_string(), guild_object);
+        let runtime = Runtime::new(context);
+
+        assert_eq!(runtime.execute("{guild.count}").unwrap(), "7");
+
+        let program = "Hi, {guild.nmae}!";
+        let err = runtime.execute(program).unwrap_err();
+        assert!(matches!(err, RuntimeError::UndefinedProperty { .. }));
+        assert_eq!(
+            err.render(program),
+            "error: Undefined property 'guild.nmae'\n --> 1:6\n  |\n1 | Hi, {guild.nmae}!\n  |      ^^^^^^^^^^"
+        );
+
+        assert!(matches!(
+            runtime.execute("{guild.name.first}"),
+            Err(RuntimeError::NotTraversable { kind: "String", .. })
+        ));
+        assert!(matches!(
+            runtime.execute("{guild}"),
+            Err(RuntimeError::Unrenderable { kind: "Object", .. })
+        ));
+        assert!(matches!(
+            runtime.execute("{nothere.name}"),
+            Err(RuntimeError::UndefinedProperty { .. })
+        ));
+        assert!(matches!(
+            runtime.execute("Hi {guild.name"),
+            Err(RuntimeError::Syntax { .. })
+        ));
+        assert!(matches!(
+            runtime.execute("Hi {guild.name # }"),
+            Err(RuntimeError::Syntax { .. })
+        ));
+    }
+
+    #[test]
+    fn function_arguments() {
+        let mut context = Context::new();
+        let mut guild_object: ObjectHashMap = HashMap::new();
+        guild_object.insert("count".to_string(), Value::Int(3));
+        guild_object.insert("name".to_string(), Value::String("BarFight".to_string()));
+        context.add_object("guild".to_string(), guild_object);
+        context.0.insert(
+            "toPlacement".to_string(),
+            Value::function(|args| match args.first() {
+                Some(Value::Int(int)) => Some(Value::String(format!("{}rd", int))),
+                _ => None,
+            }),
+        );
+        context.0.insert(
+            "describe".to_string(),
+            Value::function(|args| {
+                Some(Value::String(
+                    args.iter().map(Value::kind).collect::<Vec<_>>().join(","),
+                ))
+            }),
+        );
+        let runtime = Runtime::new(context);
+
+        assert_eq!(
+            runtime.execute("{toPlacement | guild.count}").unwrap(),
+            "3rd"
+        );
+        assert_eq!(
+            runtime
+                .execute("{describe | 5 ; \"five\" ; guild.name ; guild ; toPlacement}")
+                .unwrap(),
+            "Int,String,String,Object,Function"
+        );
+        assert!(matches!(
+            runtime.execute("{toPlacement | guild.cuont}"),
+            Err(RuntimeError::UndefinedProperty { .. })
+        ));
+    }
+
+    #[test]
+    fn value_rendering() {
+        let mut context = Context::new();
+        let mut user_object: ObjectHashMap = HashMap::new();
+        user_object.insert("roles".to_string(), vec!["admin", "mod", "member"].into());
+        user_object.insert("nick".to_string(), None::<String>.into());
+        user_object.insert("level".to_string(), 4.5.into());
+        user_object.insert("balance".to_string(), (-20i64).into());
+        user_object.insert("bot".to_string(), false.into());
+        user_object.insert("joined".to_string(), Timestamp::from_unix(0).into());
+        user_object.insert(
+            "objects".to_string(),
+            Value::List(vec![Value::Object(HashMap::new())]),
+        );
+        context.add_object("user".to_string(), user_object);
+        let mut runtime = Runtime::new(context);
+
+        assert_eq!(
+            runtime
+                .execute(
+                    "{user.roles}|{user.nick}|{user.level}|{user.balance}|{user.bot}|{user.joined}"
+                )
+                .unwrap(),
+            "admin, mod, member||4.5|-20|false|1970-01-01T00:00:00Z"
+        );
+        assert!(matches!(
+            runtime.execute("{user.objects}"),
+            Err(RuntimeError::Unrenderable { kind: "List", .. })
+        ));
+
+        runtime.options.format.list_last_separator = Some(" and ".to_string());
+        runtime.options.format.null = "nobody".to_string();
+        assert_eq!(
+            runtime.execute("{user.roles}, {user.nick}").unwrap(),
+            "admin, mod and member, nobody"
+        );
+    }
+
+    /// Minimal executor, gives up instead of hanging when the future never completes
+    fn block_on<F: Future>(future: F) -> F::Output {
+        use std::task::{Context, Poll, Wake, Waker};
+
+        struct NoopWaker;
+        impl Wake for NoopWaker {
+            fn wake(self: Arc<Self>) {}
+        }
+
+        let waker = Waker::from(Arc::new(NoopWaker));
+        let mut cx = Context::from_waker(&waker);
+        let mut future = std::pin::pin!(future);
+        for _ in 0..100 {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+        }
+        panic!("future did not complete")
+    }
+
+    #[test]
+    fn async_functions() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::task::Poll;
+
+        let started = Arc::new(AtomicUsize::new(0));
+        // Only completes once both calls are in flight, so it hangs if calls are awaited one by one
+        let fetch = |started: Arc<AtomicUsize>, value: i64| {
+            Value::async_function(move |_args| {
+                let started = Arc::clone(&started);
+                let mut counted = false;
+                async move {
+                    std::future::poll_fn(|_| {
+                        if !counted {
+                            counted = true;
+                            started.fetch_add(1, Ordering::SeqCst);
+                        }
+                        if started.load(Ordering::SeqCst) < 2 {
+                            Poll::Pending
+                        } else {
+                            Poll::Ready(Some(Value::Int(value)))
+                        }
+                    })
+                    .await
+                }
+            })
+        };
+
+        let mut context = Context::new();
+        let mut guild_object: ObjectHashMap = HashMap::new();
+        guild_object.insert("name".to_string(), "BarFight".into());
+        guild_object.insert("members".to_string(), fetch(Arc::clone(&started), 120));
+        context.add_object("guild".to_string(), guild_object);
+        context
+            .0
+            .insert("level".to_string(), fetch(Arc::clone(&started), 7));
+        let runtime = Runtime::new(context);
+
+        let template = runtime
+            .compile("{guild.name}: {guild.members}, level {level | 1}")
+            .unwrap();
+        // Has to be spawnable on multi-threaded executors
+        let _: &dyn Send = &runtime.render_async(&template);
+        assert_eq!(
+            block_on(runtime.render_async(&template)).unwrap(),
+            "BarFight: 120, level 7"
+        );
+
+        assert!(matches!(
+            runtime.render(&template),
+            Err(RuntimeError::AsyncFunction { .. })
+        ));
+
+        // Each tag looks its property up once, async function or not
+        struct Counting(Arc<AtomicUsize>);
+        impl Resolver for Counting {
+            fn get(&self, segment: &str) -> Option<Value> {
+                self.0.fetch_add(1, Ordering::SeqCst);
+                match segment {
+                    "name" => Some("BarFight".into()),
+                    "members" => Some(Value::async_function(|_args| async {
+                        Some(Value::Int(120))
+                    })),
+                    _ => None,
+                }
+            }
+        }
+        let lookups = Arc::new(AtomicUsize::new(0));
+        let mut context = Context::new();
+        context.0.insert(
+            "guild".to_string(),
+            Value::lazy(Counting(Arc::clone(&lookups))),
+        );
+        let runtime = Runtime::new(context);
+        let template = runtime.compile("{guild.name}: {guild.members}").unwrap();
+        assert_eq!(
+            block_on(runtime.render_async(&template)).unwrap(),
+            "BarFight: 120"
+        );
+        assert_eq!(lookups.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn undefined_modes() {
+        let mut context = Context::new();
+        let mut user_object: ObjectHashMap = HashMap::new();
+        user_object.insert("name".to_string(), "Ferris".into());
+        context.add_object("user".to_string(), user_object);
+        context
+            .0
+            .insert("echo".to_string(), Value::function(|mut args| args.pop()));
+        let mut runtime = Runtime::new(context);
+        let template = runtime
+            .compile("Hi {user.name}{user.foo}/{echo | user.name.first}")
+            .unwrap();
+
+        assert!(matches!(
+            runtime.render(&template),
+            Err(RuntimeError::UndefinedProperty { .. })
+        ));
+
+        runtime.options.undefined = UndefinedMode::Empty;
+        let rendered = runtime.render_with_warnings(&template).unwrap();
+        assert_eq!(rendered.output, "Hi Ferris/");
+        assert!(matches!(
+            rendered.warnings.as_slice(),
+            [
+                RuntimeError::UndefinedProperty { .. },
+                RuntimeError::NotTraversable { .. }
+            ]
+        ));
+
+        runtime.options.undefined = UndefinedMode::KeepTag;
+        assert_eq!(
+            runtime.render(&template).unwrap(),
+            "Hi Ferris{user.foo}/{echo | user.name.first}"
+        );
+
+        // Only undefined values are lenient
+        assert!(matches!(
+            runtime.execute("{user}"),
+            Err(RuntimeError::Unrenderable { .. })
+        ));
+    }
+
+    #[test]
+    fn escaped_values() {
+        let mut context = Context::new();
+        let mut user_object: ObjectHashMap = HashMap::new();
+        user_object.insert("nick".to_string(), "**@everyone**".into());
+        context.add_object("user".to_string(), user_object);
+        context
+            .0
+            .insert("echo".to_string(), Value::function(|mut args| args.pop()));
+        let mut runtime = Runtime::new(context);
+        runtime.options.escape = Escape::Discord;
+
+        assert_eq!(
+            runtime
+                .execute("**Hi** {user.nick}/{echo | user.nick}/{!user.nick}")
+                .unwrap(),
+            "**Hi** \\*\\*@\u{200B}everyone\\*\\*/\\*\\*@\u{200B}everyone\\*\\*/**@everyone**"
+        );
+    }
+
+    #[test]
+    fn render_limits() {
+        let mut context = Context::new();
+        context
+            .0
+            .insert("echo".to_string(), Value::function(|mut args| args.pop()));
+        context.0.insert(
+            "slow".to_string(),
+            Value::function(|_| {
+                std::thread::sleep(Duration::from_millis(20));
+                Some(Value::Int(1))
+            }),
+        );
+        let mut runtime = Runtime::new(context);
+        let template = runtime
+            .compile("{echo | \"0123456789\"}{echo | \"0123456789\"}")
+            .unwrap();
+        assert!(runtime.render(&template).is_ok());
+
+        runtime.options.limits.max_output_bytes = Some(15);
+        let err = runtime.render(&template).unwrap_err();
+        assert!(matches!(err, RuntimeError::OutputLimit { limit: 15, .. }));
+        // Points at the tag that went over
+        assert_eq!(err.start().line, 21);
+
+        runtime.options.limits = Limits {
+            max_function_calls: Some(1),
+            ..Limits::default()
+        };
+        assert!(matches!(
+            runtime.render(&template),
+            Err(RuntimeError::CallLimit { limit: 1, .. })
+        ));
+
+        runtime.options.limits = Limits {
+            timeout: Some(Duration::from_millis(5)),
+            ..Limits::default()
+        };
+        assert!(matches!(
+            runtime.execute("{slow}, after"),
+            Err(RuntimeError::Timeout { .. })
+        ));
+    }
 }
@@ -0,0 +1,126 @@
+//! Conversions from `serde` types, enabled with the `serde` feature.
+
+use crate::runtime::{Context, ObjectHashMap, Value};
+use serde::{ser::Error, Serialize};
+
+impl From<serde_json::Value> for Value {
+    /// Numbers that fit in an `Int` become one, other numbers (fractional or over `i64::MAX`)
+    /// become a `Float`.
+    fn from(value: serde_json::Value) -> Self {
+        match value {
+            serde_json::Value::Bool(bool) => Value::Bool(bool),
+            serde_json::Value::Number(number) => match number.as_i64() {
+                Some(int) => Value::Int(int),
+                None => Value::Float(number.as_f64().unwrap_or(f64::NAN)),
+            },
+            serde_json::Value::String(string) => Value::String(string),
+            serde_json::Value::Array(items) => {
+                Value::List(items.into_iter().map(Value::from).collect())
+            }
+            serde_json::Value::Object(map) => Value::Object(object_from_map(map)),
+            serde_json::Value::Null => Value::Null,
+        }
+    }
+}
+
+fn object_from_map(map: serde_json::Map<String, serde_json::Value>) -> ObjectHashMap {
+    map.into_iter()
+        .map(|(key, value)| (key, Value::from(value)))
+        .collect()
+}
+
+impl Context {
+    /// Builds a context out of any value that serializes to a map, its fields become the globals.
+    ///
+    /// ```
+    /// # use jtl_runtime::runtime::{Context, Runtime};
+    /// #[derive(serde::Serialize)]
+    /// struct Guild {
+    ///     name: String,
+    /// }
+    ///
+    /// #[derive(serde::Serialize)]
+    /// struct Globals {
+    ///     guild: Guild,
+    /// }
+    ///
+    /// let context = Context::from_serialize(&Globals {
+    ///     guild: Guild { name: "BarFight".to_string() },
+    /// })
+    /// .unwrap();
+    /// let runtime = Runtime::new(context);
+    /// assert_eq!(runtime.execute("{guild.name}").unwrap(), "BarFight");
+    /// ```
+    pub fn from_serialize<T: Serialize>(value: &T) -> Result<Self, serde_json::Error> {
+        match serde_json::to_value(value)? {
+            serde_json::Value::Object(map) => Ok(Context(object_from_map(map))),
+            _ => Err(serde_json::Error::custom(
+                "a context can only be made out of a map or struct",
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::Runtime;
+    use serde::Serialize;
+
+    #[derive(Serialize)]
+    struct User {
+        mention: String,
+        nick: Option<String>,
+        roles: Vec<String>,
+        bot: bool,
+    }
+
+    #[derive(Serialize)]
+    struct Guild {
+        name: String,
+        count: u32,
+        balance: i64,
+    }
+
+    #[derive(Serialize)]
+    struct Globals {
+        user: User,
+        guild: Guild,
+    }
+
+    #[test]
+    fn context_from_serialize() {
+        let context = Context::from_serialize(&Globals {
+            user: User {
+                mention: "<@1>".to_string(),
+                nick: None,
+                roles: vec!["admin".to_string()],
+                bot: false,
+            },
+            guild: Guild {
+                name: "BarFight".to_string(),
+                count: 42,
+                balance: -5,
+            },
+        })
+        .unwrap();
+
+        let Some(Value::Object(user)) = context.get("user") else {
+            panic!("structs should be objects")
+        };
+        assert!(matches!(user.get("nick"), Some(Value::Null)));
+        assert!(matches!(user.get("roles"), Some(Value::List(roles)) if roles.len() == 1));
+
+        let runtime = Runtime::new(context);
+        assert_eq!(
+            runtime
+                .execute(
+                    "{user.mention} {guild.name}{guild.count}{guild.balance}{user.bot}{user.nick}"
+                )
+                .unwrap(),
+            "<@1>BarFight42-5false"
+        );
+
+        assert!(Context::from_serialize(&vec![1, 2]).is_err());
+    }
+}
@@ -0,0 +1,654 @@
+//! Opt-in standard library of string, number and date functions.
+//!
+//! ```
+//! # use jtl_runtime::{runtime::{Context, Runtime}, stdlib};
+//! let mut context = Context::new();
+//! stdlib::register(&mut context);
+//!
+//! let runtime = Runtime::new(context);
+//! assert_eq!(runtime.execute("{toPlacement | 3}").unwrap(), "3rd");
+//! ```
+//!
+//! Functions return no value (rendered as "(NONE)") when given arguments they can't use.
+
+use crate::date::{Clock, SystemClock, Timestamp};
+use crate::error::RuntimeError;
+use crate::locale::{Locale, PluralCategory};
+use crate::runtime::{Call, Context, Value, ValueFormat};
+#[cfg(feature = "schema")]
+use jtl_service::{SFunction, SGlobal, SchemaService};
+#[cfg(feature = "schema")]
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// A function of the library, with what the schema says about it
+#[cfg_attr(not(feature = "schema"), allow(dead_code))]
+struct Builtin {
+    /// Name in the context
+    name: &'static str,
+    /// Name of the function in the schema
+    schema_name: &'static str,
+    description: &'static str,
+    arguments: &'static [&'static str],
+    return_type: &'static str,
+    func: Func,
+}
+
+enum Func {
+    Pure(fn(Vec<Value>) -> Option<Value>),
+    /// Also given the time of the call, from the registered `Clock`
+    Clocked(fn(Vec<Value>, Timestamp) -> Option<Value>),
+    /// Also given the locale of the render
+    Localized(fn(Vec<Value>, &Locale) -> Option<Value>),
+    /// Given the call itself, for what needs more than the locale
+    Call(fn(Vec<Value>, &mut Call) -> Result<Option<Value>, RuntimeError>),
+}
+
+/// Widest `pad` goes, more than any chat message holds. The padding is built before
+/// `Limits::max_output_bytes` is checked, so `{pad | "" ; 4000000000}` would take gigabytes
+const MAX_PAD_WIDTH: usize = 10_000;
+
+const BUILTINS: &[Builtin] = &[
+    Builtin {
+        name: "upper",
+        schema_name: "Upper",
+        description: "Converts text to uppercase",
+        arguments: &["String"],
+        return_type: "String",
+        func: Func::Pure(upper),
+    },
+    Builtin {
+        name: "lower",
+        schema_name: "Lower",
+        description: "Converts text to lowercase",
+        arguments: &["String"],
+        return_type: "String",
+        func: Func::Pure(lower),
+    },
+    Builtin {
+        name: "title",
+        schema_name: "Title",
+        description: "Capitalizes the first letter of every word",
+        arguments: &["String"],
+        return_type: "String",
+        func: Func::Pure(title),
+    },
+    Builtin {
+        name: "trim",
+        schema_name: "Trim",
+        description: "Removes the whitespace around text",
+        arguments: &["String"],
+        return_type: "String",
+        func: Func::Pure(trim),
+    },
+    Builtin {
+        name: "truncate",
+        schema_name: "Truncate",
+        description: "Shortens text to at most 'length' characters, ending with the suffix (\"…\" by default)",
+        arguments: &["String", "Int", "String?"],
+        return_type: "String",
+        func: Func::Pure(truncate),
+    },
+    Builtin {
+        name: "pad",
+        schema_name: "Pad",
+        description: "Pads the start of text up to 'width' characters (10000 at most), with spaces or the given character",
+        arguments: &["String", "Int", "String?"],
+        return_type: "String",
+        func: Func::Pure(pad),
+    },
+    Builtin {
+        name: "replace",
+        schema_name: "Replace",
+        description: "Replaces every occurrence of 'from' with 'to'",
+        arguments: &["String", "String", "String"],
+        return_type: "String",
+        func: Func::Pure(replace),
+    },
+    Builtin {
+        name: "pluralize",
+        schema_name: "Pluralize",
+        description: "The form of the word for a count, picked by the plural rules of the locale: 'one', 'other' (word + \"s\" by default), then 'few' and 'many' for the languages having them",
+        arguments: &["Int", "String", "String?", "String?", "String?"],
+        return_type: "String",
+        func: Func::Localized(pluralize),
+    },
+    Builtin {
+        name: "toPlacement",
+        schema_name: "ToPlacement",
+        description: "Converts number into placement",
+        arguments: &["Int"],
+        return_type: "String",
+        func: Func::Localized(ordinal),
+    },
+    Builtin {
+        name: "ordinal",
+        schema_name: "Ordinal",
+        description: "Converts number into placement, aka: 1st, 2nd, 3rd in English",
+        arguments: &["Int"],
+        return_type: "String",
+        func: Func::Localized(ordinal),
+    },
+    Builtin {
+        name: "round",
+        schema_name: "Round",
+        description: "Rounds a number to the given number of decimals (none by default)",
+        arguments: &["Float", "Int?"],
+        return_type: "Float",
+        func: Func::Pure(round),
+    },
+    Builtin {
+        name: "formatNumber",
+        schema_name: "FormatNumber",
+        description: "Groups the thousands of a number with the separators of the locale, aka: 1,234,567",
+        arguments: &["Float"],
+        return_type: "String",
+        func: Func::Localized(format_number),
+    },
+    Builtin {
+        name: "length",
+        schema_name: "Length",
+        description: "Number of characters in text or items in a list",
+        arguments: &["String | List"],
+        return_type: "Int",
+        func: Func::Pure(length),
+    },
+    Builtin {
+        name: "join",
+        schema_name: "Join",
+        description: "Joins the items of a list with the separator (\", \" by default)",
+        arguments: &["List", "String?"],
+        return_type: "String",
+        func: Func::Pure(join),
+    },
+    Builtin {
+        name: "formatDate",
+        schema_name: "FormatDate",
+        description: "Formats a date (or unix seconds) with strftime-style specifiers, \"%Y-%m-%d %H:%M\" by default",
+        arguments: &["Date", "String?"],
+        return_type: "String",
+        func: Func::Pure(format_date),
+    },
+    Builtin {
+        name: "relative",
+        schema_name: "Relative",
+        description: "How long ago a date (or unix seconds) is, aka: \"3 days ago\", \"in 2 hours\"",
+        arguments: &["Date"],
+        return_type: "String",
+        func: Func::Clocked(relative),
+    },
+    Builtin {
+        name: "discordTimestamp",
+        schema_name: "DiscordTimestamp",
+        description: "A date (or unix seconds) as a Discord timestamp, shown in the local time of each reader. Styles are t, T, d, D, f, F and R",
+        arguments: &["Date", "String?"],
+        return_type: "String",
+        func: Func::Pure(discord_timestamp),
+    },
+    Builtin {
+        name: "t",
+        schema_name: "Translate",
+        description: "The message of the locale for the key, rendered against the context",
+        arguments: &["String"],
+        return_type: "String",
+        func: Func::Call(translate),
+    },
+];
+
+/// Adds every function of the library to `context`, replacing values with the same name
+pub fn register(context: &mut Context) {
+    register_with_clock(context, Arc::new(SystemClock));
+}
+
+/// Same as `register`, with date functions (aka: `relative`) reading the time from `clock`
+pub fn register_with_clock(context: &mut Context, clock: Arc<dyn Clock>) {
+    for builtin in BUILTINS {
+        let func = match builtin.func {
+            Func::Pure(func) => Value::function(func),
+            Func::Clocked(func) => {
+                let clock = Arc::clone(&clock);
+                Value::function(move |args| func(args, clock.now()))
+            }
+            Func::Localized(func) => {
+                Value::localized(move |args, call| Ok(func(args, call.locale())))
+            }
+            Func::Call(func) => Value::localized(func),
+        };
+        context.0.insert(builtin.name.to_string(), func);
+    }
+}
+
+/// Describes the library for the language server, merge it into the schema of the rest of the context
+#[cfg(feature = "schema")]
+pub fn schema() -> SchemaService {
+    let global = BUILTINS
+        .iter()
+        .map(|builtin| {
+            SGlobal(
+                builtin.name.to_string(),
+                format!("@{}", builtin.schema_name),
+            )
+        })
+        .collect();
+    let functions = BUILTINS
+        .iter()
+        .map(|builtin| {
+            let function = SFunction {
+                description: vec![builtin.description.to_string()],
+                arguments: builtin.arguments.iter().map(|a| a.to_string()).collect(),
+                return_type: builtin.return_type.to_string(),
+            };
+            (builtin.schema_name.to_string(), function)
+        })
+        .collect();
+
+    SchemaService {
+        v: "1.0.0".to_string(),
+        global,
+        structures: HashMap::new(),
+        functions,
+    }
+}
+
+/// Any value with a text form, so `{upper | guild.count}` works too
+fn text(value: Option<&Value>) -> Option<String> {
+    value?.render(&ValueFormat::default())
+}
+
+fn number(value: Option<&Value>) -> Option<f64> {
+    match value? {
+        Value::Int(int) => Some(*int as f64),
+        Value::Float(float) => Some(*float),
+        _ => None,
+    }
+}
+
+fn int(value: Option<&Value>) -> Option<i64> {
+    match value? {
+        Value::Int(int) => Some(*int),
+        _ => None,
+    }
+}
+
+/// Dates, or unix seconds as they come from most APIs
+fn date(value: Option<&Value>) -> Option<Timestamp> {
+    match value? {
+        Value::Date(timestamp) => Some(*timestamp),
+        Value::Int(int) => Some(Timestamp::from_unix(*int)),
+        Value::Float(float) if float.is_finite() => Some(Timestamp::from_unix(*float as i64)),
+        _ => None,
+    }
+}
+
+pub(crate) fn upper(args: Vec<Value>) -> Option<Value> {
+    Some(Value::String(text(args.first())?.to_uppercase()))
+}
+
+pub(crate) fn lower(args: Vec<Value>) -> Option<Value> {
+    Some(Value::String(text(args.first())?.to_lowercase()))
+}
+
+fn title(args: Vec<Value>) -> Option<Value> {
+    let mut titled = String::new();
+    let mut word_start = true;
+    for char in text(args.first())?.chars() {
+        if word_start {
+            titled.extend(char.to_uppercase());
+        } else {
+            titled.extend(char.to_lowercase());
+        }
+        word_start = char.is_whitespace();
+    }
+
+    Some(Value::String(titled))
+}
+
+pub(crate) fn trim(args: Vec<Value>) -> Option<Value> {
+    Some(Value::String(text(args.first())?.trim().to_string()))
+}
+
+fn truncate(args: Vec<Value>) -> Option<Value> {
+    let string = text(args.first())?;
+    let length = usize::try_from(int(args.get(1))?).ok()?;
+    let suffix = text(args.get(2)).unwrap_or_else(|| "…".to_string());
+
+    if string.chars().count() <= length {
+        return Some(Value::String(string));
+    }
+    // The suffix counts toward the length, what is left of it when it doesn't fit
+    let Some(kept) = length.checked_sub(suffix.chars().count()) else {
+        return Some(Value::String(suffix.chars().take(length).collect()));
+    };
+    let mut truncated: String = string.chars().take(kept).collect();
+    truncated.push_str(&suffix);
+
+    Some(Value::String(truncated))
+}
+
+fn pad(args: Vec<Value>) -> Option<Value> {
+    let string = text(args.first())?;
+    let width = usize::try_from(int(args.get(1))?).ok()?;
+    if width > MAX_PAD_WIDTH {
+        return None;
+    }
+    let fill = match text(args.get(2)) {
+        Some(fill) => fill.chars().next()?,
+        None => ' ',
+    };
+
+    let missing = width.saturating_sub(string.chars().count());
+    let mut padded: String = std::iter::repeat_n(fill, missing).collect();
+    padded.push_str(&string);
+
+    Some(Value::String(padded))
+}
+
+fn replace(args: Vec<Value>) -> Option<Value> {
+    let string = text(args.first())?;
+    let from = text(args.get(1))?;
+    let to = text(args.get(2))?;
+    if from.is_empty() {
+        return Some(Value::String(string));
+    }
+
+    Some(Value::String(string.replace(&from, &to)))
+}
+
+fn pluralize(args: Vec<Value>, locale: &Locale) -> Option<Value> {
+    let count = number(args.first())?;
+    let one = text(args.get(1))?;
+    let other = || text(args.get(2)).unwrap_or_else(|| format!("{}s", one));
+
+    let form = match locale.plural(count) {
+        PluralCategory::One => one.clone(),
+        PluralCategory::Few => text(args.get(3)).unwrap_or_else(other),
+        PluralCategory::Many => text(args.get(4)).unwrap_or_else(other),
+        PluralCategory::Zero | PluralCategory::Two | PluralCategory::Other => other(),
+    };
+    Some(Value::String(form))
+}
+
+pub(crate) fn ordinal(args: Vec<Value>, locale: &Locale) -> Option<Value> {
+    Some(Value::String(locale.ordinal(int(args.first())?)))
+}
+
+pub(crate) fn round(args: Vec<Value>) -> Option<Value> {
+    if let Some(Value::Int(int)) = args.first() {
+        return Some(Value::Int(*int));
+    }
+    let float = number(args.first())?;
+    let decimals = args.get(1).map_or(Some(0), |value| int(Some(value)))?;
+
+    let scale = 10f64.powi(decimals.clamp(0, 15) as i32);
+    Some(Value::Float((float * scale).round() / scale))
+}
+
+pub(crate) fn format_number(args: Vec<Value>, locale: &Locale) -> Option<Value> {
+    Some(Value::String(locale.format_number(args.first()?)?))
+}
+
+pub(crate) fn length(args: Vec<Value>) -> Option<Value> {
+    let length = match args.first()? {
+        Value::List(items) => items.len(),
+        value => text(Some(value))?.chars().count(),
+    };
+
+    Some(Value::from(length))
+}
+
+fn join(args: Vec<Value>) -> Option<Value> {
+    let Some(Value::List(items)) = args.first() else {
+        return None;
+    };
+    let separator = text(args.get(1)).unwrap_or_else(|| ", ".to_string());
+    let items = items
+        .iter()
+        .map(|item| text(Some(item)))
+        .collect::<Option<Vec<_>>>()?;
+
+    Some(Value::String(items.join(&separator)))
+}
+
+fn format_date(args: Vec<Value>) -> Option<Value> {
+    let timestamp = date(args.first())?;
+    let pattern = text(args.get(1)).unwrap_or_else(|| "%Y-%m-%d %H:%M".to_string());
+
+    Some(Value::String(timestamp.format(&pattern)))
+}
+
+fn relative(args: Vec<Value>, now: Timestamp) -> Option<Value> {
+    Some(Value::String(date(args.first())?.relative_to(now)))
+}
+
+fn discord_timestamp(args: Vec<Value>) -> Option<Value> {
+    let timestamp = date(args.first())?;
+    match text(args.get(1)).as_deref() {
+        None => Some(Value::String(format!("<t:{}>", timestamp.unix()))),
+        Some(style @ ("t" | "T" | "d" | "D" | "f" | "F" | "R")) => {
+            Some(Value::String(format!("<t:{}:{}>", timestamp.unix(), style)))
+        }
+        Some(_) => None,
+    }
+}
+
+fn translate(args: Vec<Value>, call: &mut Call) -> Result<Option<Value>, RuntimeError> {
+    let Some(key) = text(args.first()) else {
+        return Ok(None);
+    };
+    Ok(Some(Value::String(call.message(&key)?)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::date::FixedClock;
+    use crate::escape::Escape;
+    use crate::runtime::{Limits, ObjectHashMap, Runtime};
+    use std::collections::HashMap;
+
+    fn runtime() -> Runtime {
+        let mut context = Context::new();
+        register(&mut context);
+        let mut guild_object: ObjectHashMap = HashMap::new();
+        guild_object.insert("name".to_string(), " the bar FIGHT ".into());
+        guild_object.insert("count".to_string(), 1234567u32.into());
+        guild_object.insert("ratio".to_string(), 0.456.into());
+        guild_object.insert("roles".to_string(), vec!["a", "b", "c"].into());
+        context.add_object("guild".to_string(), guild_object);
+        Runtime::new(context)
+    }
+
+    #[test]
+    fn builtins() {
+        let runtime = runtime();
+        let render = |program: &str| runtime.execute(program).unwrap();
+
+        assert_eq!(render("{upper | \"hi\"}"), "HI");
+        assert_eq!(render("{lower | \"HI\"}"), "hi");
+        assert_eq!(render("{title | guild.name}"), " The Bar Fight ");
+        assert_eq!(render("{trim | guild.name}"), "the bar FIGHT");
+        assert_eq!(render("{truncate | \"welcome\" ; 5}"), "welc…");
+        assert_eq!(render("{truncate | \"welcome\" ; 5 ; \"..\"}"), "wel..");
+        assert_eq!(render("{truncate | \"hi\" ; 5}"), "hi");
+        assert_eq!(render("{truncate | \"welcome\" ; 0}"), "");
+        assert_eq!(render("{truncate | \"welcome\" ; 1 ; \"..\"}"), ".");
+        assert_eq!(render("{pad | 7 ; 3 ; \"0\"}"), "007");
+        assert_eq!(render("{pad | \"a\" ; 4000000000}"), "(NONE)");
+        assert_eq!(render("{replace | \"a-b-c\" ; \"-\" ; \"+\"}"), "a+b+c");
+        assert_eq!(render("{pluralize | 1 ; \"member\"}"), "member");
+        assert_eq!(render("{pluralize | guild.count ; \"member\"}"), "members");
+        assert_eq!(render("{pluralize | 2 ; \"mouse\" ; \"mice\"}"), "mice");
+        assert_eq!(
+            render("{toPlacement | 1}{ordinal | 2}{ordinal | 3}{ordinal | 11}{ordinal | 112}{ordinal | 23}"),
+            "1st2nd3rd11th112th23rd"
+        );
+        assert_eq!(render("{round | guild.ratio ; 2}"), "0.46");
+        assert_eq!(render("{round | guild.ratio}"), "0");
+        assert_eq!(render("{formatNumber | guild.count}"), "1,234,567");
+        assert_eq!(render("{formatNumber | 999}"), "999");
+        assert_eq!(render("{length | guild.roles}"), "3");
+        assert_eq!(render("{length | \"héllo\"}"), "5");
+        assert_eq!(render("{join | guild.roles ; \"/\"}"), "a/b/c");
+        assert_eq!(render("{join | guild.roles}"), "a, b, c");
+
+        assert_eq!(render("{ordinal | \"first\"}"), "(NONE)");
+    }
+
+    #[test]
+    fn date_builtins() {
+        let mut context = Context::new();
+        // Tuesday 14th of November 2023, 22:13:20
+        let now = Timestamp::from_unix(1_700_000_000);
+        register_with_clock(&mut context, Arc::new(FixedClock(now)));
+        let mut user_object: ObjectHashMap = HashMap::new();
+        user_object.insert(
+            "joined".to_string(),
+            Timestamp::from_unix(1_700_000_000 - 3 * 86_400).into(),
+        );
+        // Clamped to i64::MIN seconds
+        user_object.insert("ancient".to_string(), (-1e300).into());
+        context.add_object("user".to_string(), user_object);
+        let runtime = Runtime::new(context);
+        let render = |program: &str| runtime.execute(program).unwrap();
+
+        assert_eq!(render("{formatDate | user.joined}"), "2023-11-11 22:13");
+        assert_eq!(
+            render("{formatDate | user.joined ; \"%A %e %B\"}"),
+            "Saturday 11 November"
+        );
+        assert_eq!(render("{formatDate | 0 ; \"%Y\"}"), "1970");
+        assert_eq!(render("{relative | user.joined}"), "3 days ago");
+        assert_eq!(
+            render("{relative | user.ancient}"),
+            "292471208731 years ago"
+        );
+        assert_eq!(render("{discordTimestamp | user.joined}"), "<t:1699740800>");
+        assert_eq!(
+            render("{discordTimestamp | 1700000000 ; \"R\"}"),
+            "<t:1700000000:R>"
+        );
+        assert_eq!(render("{discordTimestamp | 0 ; \"x\"}"), "(NONE)");
+    }
+
+    #[test]
+    fn localized_builtins() {
+        let mut runtime = runtime();
+        let mut russian = Locale::bundled("ru").unwrap();
+        russian
+            .add_message("members", "{t | \"member\"}: {guild.count.formatted}")
+            .unwrap();
+        russian
+            .add_message("member", "{pluralize | guild.count ; \"участник\" ; \"участника\" ; \"участника\" ; \"участников\"}")
+            .unwrap();
+        russian.add_message("loop", "{t | \"loop\"}").unwrap();
+        runtime.options.locale = Arc::new(russian);
+        let render = |program: &str| runtime.execute(program);
+
+        assert_eq!(
+            render("{t | \"members\"}").unwrap(),
+            "участников: 1\u{A0}234\u{A0}567"
+        );
+        assert_eq!(
+            render("{pluralize | 1 ; \"файл\" ; \"файла\" ; \"файла\" ; \"файлов\"}/{pluralize | 3 ; \"файл\" ; \"файла\" ; \"файла\" ; \"файлов\"}").unwrap(),
+            "файл/файла"
+        );
+        assert_eq!(render("{ordinal | 2}").unwrap(), "2-й");
+        assert_eq!(render("{formatNumber | guild.ratio}").unwrap(), "0,456");
+
+        assert_eq!(
+            render("{t | \"welcome\"}").unwrap_err().to_string(),
+            "Message 'welcome': Not in the catalog of 'ru'"
+        );
+        assert_eq!(
+            render("{t | \"loop\"}").unwrap_err().to_string(),
+            "Message 'loop': Includes itself"
+        );
+
+        // Only the tags of a message are escaped, and only once
+        let mut english = Locale::default();
+        english
+            .add_message("welcome", "**Welcome** {guild.name}")
+            .unwrap();
+        runtime.options.locale = Arc::new(english);
+        runtime.options.escape = Escape::Markdown;
+        runtime.global.0.insert(
+            "guild".to_string(),
+            Value::Object(HashMap::from([("name".to_string(), "a_b".into())])),
+        );
+        assert_eq!(
+            runtime.execute("{t | \"welcome\"}").unwrap(),
+            "**Welcome** a\\_b"
+        );
+    }
+
+    #[test]
+    fn message_limits() {
+        let mut runtime = runtime();
+        // Each message includes the next one 10 times, "a" ends up 10000 bytes long
+        let mut english = Locale::default();
+        for (key, next) in [("a", "b"), ("b", "c"), ("c", "d")] {
+            let include = format!("{{t | \"{}\"}}", next);
+            english.add_message(key, &include.repeat(10)).unwrap();
+        }
+        english.add_message("d", "xxxxxxxxxx").unwrap();
+        runtime.options.locale = Arc::new(english);
+        assert_eq!(runtime.execute("{t | \"a\"}").unwrap().len(), 10000);
+
+        runtime.options.limits = Limits {
+            max_message_depth: Some(3),
+            ..Limits::default()
+        };
+        assert_eq!(runtime.execute("{t | \"b\"}").unwrap().len(), 1000);
+        let err = runtime.execute("Hi {t | \"a\"}").unwrap_err();
+        assert!(matches!(err, RuntimeError::MessageDepth { limit: 3, .. }));
+        assert_eq!(err.start().line, 4);
+
+        // Counted as the messages render, before the 10000 bytes are written out
+        runtime.options.limits = Limits {
+            max_output_bytes: Some(500),
+            ..Limits::default()
+        };
+        let err = runtime.execute("Hi {t | \"a\"}").unwrap_err();
+        assert!(matches!(err, RuntimeError::OutputLimit { limit: 500, .. }));
+        assert_eq!(err.start().line, 4);
+        // So the render stops long before getting through the 1111 calls of "a"
+        runtime.options.limits.max_function_calls = Some(100);
+        assert!(matches!(
+            runtime.execute("{t | \"a\"}"),
+            Err(RuntimeError::OutputLimit { .. })
+        ));
+    }
+
+    #[test]
+    fn format_number_floats() {
+        let english = Locale::default();
+        assert!(format_number(vec![Value::Float(f64::NAN)], &english).is_none());
+
+        let Some(Value::String(formatted)) = format_number(vec![Value::Float(-12345.5)], &english)
+        else {
+            panic!("floats should be formatted")
+        };
+        assert_eq!(formatted, "-12,345.5");
+    }
+
+    #[cfg(feature = "schema")]
+    #[test]
+    fn schema_matches_functions() {
+        let schema = schema();
+        let mut context = Context::new();
+        register(&mut context);
+
+        assert_eq!(schema.global.len(), context.0.len());
+        for SGlobal(key, value) in &schema.global {
+            assert!(matches!(
+                context.get(key),
+                Some(Value::Function(_) | Value::Localized(_))
+            ));
+            assert!(schema.functions.contains_key(&value[1..]));
+        }
+        assert_eq!(
+            schema.functions["ToPlacement"].signature(),
+            "(Int) -> String"
+        );
+    }
+}
@@ -0,0 +1,243 @@
+use crate::{
+    error::RuntimeError,
+    output::{IoWriter, Output},
+    resolver::Resolver,
+    runtime::{RenderOptions, Renderer},
+    trace::Traced,
+};
+use jtl_parser::{
+    lex::Lexer,
+    parser::{Parser, Source},
+};
+use std::{fmt, io};
+
+/// A lexed, parsed and validated template.
+///
+/// Compiling only happens once, rendering can be done against any `Context` (or other `Resolver`), any number of times.
+/// A template is immutable and `Send + Sync`, so it can be kept in a shared cache.
+#[derive(Debug)]
+pub struct Template {
+    source: String,
+    ast: Source,
+}
+
+impl Template {
+    /// Returns every syntax error of `program` when it is not valid
+    pub fn compile(program: &str) -> Result<Self, Vec<RuntimeError>> {
+        let mut parser = Parser::from_lexer(Lexer::from_source(program));
+        let parse_results = parser.parse();
+
+        if !parse_results.errors.is_empty() {
+            return Err(parse_results
+                .errors
+                .iter()
+                .map(RuntimeError::from)
+                .collect());
+        }
+
+        Ok(Template {
+            source: program.to_string(),
+            ast: parse_results.ast,
+        })
+    }
+
+    /// The template as it was written, errors point into it
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn ast(&self) -> &Source {
+        &self.ast
+    }
+
+    pub fn render(&self, context: &dyn Resolver) -> Result<String, RuntimeError> {
+        Ok(self.render_with(context, &RenderOptions::default())?.output)
+    }
+
+    /// Renders with non-default options, lenient renders also return what they glossed over
+    pub fn render_with(
+        &self,
+        context: &dyn Resolver,
+        options: &RenderOptions,
+    ) -> Result<Rendered, RuntimeError> {
+        let mut output = String::new();
+        let warnings = self.render_to(&mut output, context, options)?;
+
+        Ok(Rendered { output, warnings })
+    }
+
+    /// Writes the render as it goes, rather than building it up in a `String`. Returns the warnings.
+    ///
+    /// On error, whatever came before the failing part of the template has already been written.
+    pub fn render_to<W: fmt::Write>(
+        &self,
+        out: &mut W,
+        context: &dyn Resolver,
+        options: &RenderOptions,
+    ) -> Result<Vec<RuntimeError>, RuntimeError> {
+        let renderer = self.renderer(context, options);
+        let mut state = renderer.state();
+        let mut output = Output::new(out, options.limits.max_output_bytes);
+        renderer.process_body(&self.ast.body, &mut output, &mut state)?;
+
+        Ok(state.warnings)
+    }
+
+    /// `render_to` for an `io::Write`, aka: a file or a socket
+    pub fn write_to<W: io::Write>(
+        &self,
+        out: &mut W,
+        context: &dyn Resolver,
+        options: &RenderOptions,
+    ) -> Result<Vec<RuntimeError>, RuntimeError> {
+        let mut writer = IoWriter::new(out);
+        let result = self.render_to(&mut writer, context, options);
+
+        match (result, writer.error) {
+            (Err(RuntimeError::Write { start, end, .. }), Some(err)) => Err(RuntimeError::Write {
+                message: err.to_string(),
+                start,
+                end,
+            }),
+            (result, _) => result,
+        }
+    }
+
+    /// Renders along with a `Segment` for each text and tag, aka: to show which tag wrote what.
+    ///
+    /// Sync only, async functions fail the render as they do with `render_with`.
+    pub fn render_traced(
+        &self,
+        context: &dyn Resolver,
+        options: &RenderOptions,
+    ) -> Result<Traced, RuntimeError> {
+        let renderer = self.renderer(context, options);
+        let mut state = renderer.state();
+        state.trace = Some(Vec::new());
+        let mut output = String::new();
+        renderer.process_body(
+            &self.ast.body,
+            &mut Output::new(&mut output, options.limits.max_output_bytes),
+            &mut state,
+        )?;
+
+        Ok(Traced {
+            output,
+            warnings: state.warnings,
+            segments: state.trace.unwrap_or_default(),
+        })
+    }
+
+    /// Renders a template using async functions, see `Runtime::render_async`
+    pub async fn render_async(&self, context: &dyn Resolver) -> Result<String, RuntimeError> {
+        Ok(self
+            .render_async_with(context, &RenderOptions::default())
+            .await?
+            .output)
+    }
+
+    pub async fn render_async_with(
+        &self,
+        context: &dyn Resolver,
+        options: &RenderOptions,
+    ) -> Result<Rendered, RuntimeError> {
+        let renderer = self.renderer(context, options);
+        let mut state = renderer.state();
+        let mut output = String::new();
+        renderer
+            .process_body_async(
+                &self.ast.body,
+                &mut Output::new(&mut output, options.limits.max_output_bytes),
+                &mut state,
+            )
+            .await?;
+
+        Ok(Rendered {
+            output,
+            warnings: state.warnings,
+        })
+    }
+
+    fn renderer<'c>(
+        &'c self,
+        context: &'c dyn Resolver,
+        options: &'c RenderOptions,
+    ) -> Renderer<'c> {
+        Renderer {
+            context,
+            options,
+            source: &self.source,
+        }
+    }
+}
+
+/// The output of a render, along with the errors a lenient render didn't stop on
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rendered {
+    pub output: String,
+    pub warnings: Vec<RuntimeError>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::Context;
+    use crate::runtime::{ObjectHashMap, Value};
+    use std::{collections::HashMap, sync::Arc, thread};
+
+    fn guild_context(name: &str) -> Context {
+        let mut context = Context::new();
+        let mut guild_object: ObjectHashMap = HashMap::new();
+        guild_object.insert("name".to_string(), Value::String(name.to_string()));
+        context.add_object("guild".to_string(), guild_object);
+        context
+    }
+
+    #[test]
+    fn render_many() {
+        let template = Arc::new(Template::compile("Welcome to {guild.name}").unwrap());
+
+        let handles: Vec<_> = ["BarFight", "Tavern"]
+            .into_iter()
+            .map(|name| {
+                let template = Arc::clone(&template);
+                thread::spawn(move || template.render(&guild_context(name)).unwrap())
+            })
+            .collect();
+        let rendered: Vec<_> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+
+        assert_eq!(rendered, vec!["Welcome to BarFight", "Welcome to Tavern"]);
+    }
+
+    #[test]
+    fn compile_errors() {
+        let errors = Template::compile("{guild.} and {guild").unwrap_err();
+        assert!(errors.len() > 1);
+        assert!(errors
+            .iter()
+            .all(|err| matches!(err, RuntimeError::Syntax { .. })));
+    }
+
+    #[test]
+    fn render_to_writers() {
+        let template = Template::compile("Welcome to {guild.name}").unwrap();
+        let context = guild_context("BarFight");
+        let options = RenderOptions::default();
+
+        let mut output = String::from("> ");
+        template.render_to(&mut output, &context, &options).unwrap();
+        assert_eq!(output, "> Welcome to BarFight");
+
+        let mut bytes = Vec::new();
+        template.write_to(&mut bytes, &context, &options).unwrap();
+        assert_eq!(bytes, b"Welcome to BarFight");
+
+        // Room for the text but not the tag
+        let mut short = [0u8; 12];
+        let err = template
+            .write_to(&mut &mut short[..], &context, &options)
+            .unwrap_err();
+        assert!(matches!(err, RuntimeError::Write { .. }));
+        assert_eq!(err.to_string(), "failed to write whole buffer");
+    }
+}
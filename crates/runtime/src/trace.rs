@@ -0,0 +1,121 @@
+//! Which part of a template produced which part of its output, see `Template::render_traced`.
+
+use crate::error::RuntimeError;
+use std::ops::Range;
+
+/// What a part of the template was rendered as
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SegmentKind {
+    /// Text of the template, written as is
+    Text,
+    /// A tag writing the value of a property, aka: "{user.name}"
+    Value,
+    /// A tag writing what a function returned, aka: "{upper | user.name}"
+    Call,
+    /// A tag replaced by the fallback of a lenient `UndefinedMode`
+    Undefined,
+}
+
+/// A part of the output and the part of the template it comes from
+#[derive(Debug, Clone, PartialEq)]
+pub struct Segment {
+    /// Bytes of the output
+    pub output: Range<usize>,
+    /// Bytes of the template source, braces included for tags
+    pub source: Range<usize>,
+    pub kind: SegmentKind,
+    /// The value the tag resolved to, as `Debug` writes it. Aka: `String("Nyx")`
+    pub value: Option<String>,
+}
+
+/// The output of a traced render, with a segment for each text and tag of the template in order
+#[derive(Debug, Clone, PartialEq)]
+pub struct Traced {
+    pub output: String,
+    pub warnings: Vec<RuntimeError>,
+    pub segments: Vec<Segment>,
+}
+
+impl Traced {
+    /// The segment that wrote the byte at `offset` of the output
+    pub fn segment_at(&self, offset: usize) -> Option<&Segment> {
+        self.segments
+            .iter()
+            .find(|segment| segment.output.contains(&offset))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        runtime::{Context, ObjectHashMap, RenderOptions, UndefinedMode, Value},
+        template::Template,
+    };
+    use std::collections::HashMap;
+
+    #[test]
+    fn traced_render() {
+        let mut context = Context::new();
+        let mut user_object: ObjectHashMap = HashMap::new();
+        user_object.insert("name".to_string(), "Nyx".into());
+        context.add_object("user".to_string(), user_object);
+        context.0.insert(
+            "shout".to_string(),
+            Value::function(|args| match args.first() {
+                Some(Value::String(text)) => Some(Value::String(text.to_uppercase())),
+                _ => None,
+            }),
+        );
+        let options = RenderOptions {
+            undefined: UndefinedMode::KeepTag,
+            ..Default::default()
+        };
+
+        let source = "Hi {user.name}, {shout | user.name}! {user.age}";
+        let traced = Template::compile(source)
+            .unwrap()
+            .render_traced(&context, &options)
+            .unwrap();
+        assert_eq!(traced.output, "Hi Nyx, NYX! {user.age}");
+        assert_eq!(traced.warnings.len(), 1);
+
+        let parts: Vec<_> = traced
+            .segments
+            .iter()
+            .map(|segment| {
+                (
+                    &traced.output[segment.output.clone()],
+                    &source[segment.source.clone()],
+                    segment.kind,
+                    segment.value.as_deref(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            parts,
+            vec![
+                ("Hi ", "Hi ", SegmentKind::Text, None),
+                (
+                    "Nyx",
+                    "{user.name}",
+                    SegmentKind::Value,
+                    Some("String(\"Nyx\")")
+                ),
+                (", ", ", ", SegmentKind::Text, None),
+                (
+                    "NYX",
+                    "{shout | user.name}",
+                    SegmentKind::Call,
+                    Some("String(\"NYX\")")
+                ),
+                ("! ", "! ", SegmentKind::Text, None),
+                ("{user.age}", "{user.age}", SegmentKind::Undefined, None),
+            ]
+        );
+        assert_eq!(
+            traced.segment_at(9).map(|segment| segment.kind),
+            Some(SegmentKind::Call)
+        );
+    }
+}
@@ -3,6 +3,7 @@
 // MIT License - https://github.com/microsoft/vscode-languageserver-node/blob/main/License.txt
 
 use jtl_parser::lex::PPosition;
+use lsp_types::Position;
 use std::cmp;
 
 pub struct Document {
@@ -25,7 +26,7 @@ impl Document {
     }
 
     fn get_line_offsets(&mut self) -> &Vec<u32> {
-        if self.line_offsets == None {
+        if self.line_offsets.is_none() {
             self.line_offsets = Some(compute_line_offsets(&self.content, true, None));
         }
 
@@ -37,19 +38,56 @@ impl Document {
     }
     pub fn position_at(&self) {}
 
+    fn line(&self, line: usize) -> &str {
+        self.content.split('\n').nth(line).unwrap_or("")
+    }
+
+    /// LSP positions count UTF-16 code units in the line, aka: an emoji is 2 of them
+    pub fn from_lsp(&self, position: Position) -> PPosition {
+        let mut units = 0;
+        let character = self
+            .line(position.line as usize)
+            .chars()
+            .take_while(|char| {
+                units += char.len_utf16() as u32;
+                units <= position.character
+            })
+            .count();
+
+        // NOTE: PPosition's 'column' is the line and 'line' is the character in the line
+        PPosition {
+            column: position.line as usize,
+            line: character,
+        }
+    }
+
+    /// Same as `from_lsp`, the other way around
+    pub fn to_lsp(&self, position: PPosition) -> Position {
+        let character: usize = self
+            .line(position.column)
+            .chars()
+            .take(position.line)
+            .map(char::len_utf16)
+            .sum();
+
+        Position {
+            line: position.column as u32,
+            character: character as u32,
+        }
+    }
+
     pub fn offset_at(&mut self, position: PPosition) -> u32 {
+        let content_len = self.content.chars().count() as u32;
         let line_offsets = self.get_line_offsets();
         if position.column >= line_offsets.len() {
-            return self.content.len() as u32;
-        } else if position.column < 0 {
-            return 0;
+            return content_len;
         }
 
         let line_offset = line_offsets[position.column];
         let next_line_offset = if (position.column + 1) < line_offsets.len() {
             line_offsets[position.column + 1]
         } else {
-            self.content.len() as u32
+            content_len
         };
         cmp::max(
             cmp::min(line_offset + (position.line as u32), next_line_offset),
@@ -61,11 +99,7 @@ impl Document {
 static LINE_FEED: u32 = 10; // "\n"
 static CARRIAGE_RETURN: u32 = 13; // "\r"
 
-fn compute_line_offsets(
-    text: &String,
-    is_at_line_start: bool,
-    text_offset: Option<u32>,
-) -> Vec<u32> {
+fn compute_line_offsets(text: &str, is_at_line_start: bool, text_offset: Option<u32>) -> Vec<u32> {
     let mut results = if is_at_line_start {
         vec![text_offset.unwrap_or(0)]
     } else {
@@ -76,7 +110,8 @@ fn compute_line_offsets(
 
     let mut skip_next = false;
     // println!("--- {:?} ---", text);
-    for i in 0..text.len() {
+    // Offsets are counted in characters (as `PPosition` is), not bytes
+    for i in 0..chars.len() {
         if skip_next {
             skip_next = false;
             continue;
@@ -84,7 +119,9 @@ fn compute_line_offsets(
 
         let ch = chars[i] as u32;
         if ch == LINE_FEED || ch == CARRIAGE_RETURN {
-            if ch == CARRIAGE_RETURN && (i + 1) < text.len() && u32::from(chars[i + 1]) == LINE_FEED
+            if ch == CARRIAGE_RETURN
+                && (i + 1) < chars.len()
+                && u32::from(chars[i + 1]) == LINE_FEED
             {
                 skip_next = true;
             }
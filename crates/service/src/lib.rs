@@ -4,10 +4,11 @@ use document::Document;
 use jtl_parser::{
     lex::Lexer,
     parser::{Parser, ParserResults, Source},
+    utils::signature,
 };
 use lsp_types::{
-    CompletionItem, CompletionItemKind, CompletionList, Diagnostic, DiagnosticSeverity, Position,
-    Range,
+    CompletionItem, CompletionItemKind, CompletionList, Diagnostic, DiagnosticSeverity,
+    Documentation, Position, Range,
 };
 
 use crate::parser_get_node_at::{get_node_at_offset, Node};
@@ -18,40 +19,14 @@ pub mod parser_get_node_at;
 /// Run diagnostic on source and returns errors
 pub fn diagnostic(source: String) -> Vec<Diagnostic> {
     let mut diagnostics = Vec::new();
-    let mut lexr = Lexer::from_source(&source);
-    let lexr_res = lexr.scan_tokens();
-    if lexr_res.is_err() {
-        let err_msg = lexr_res.err().unwrap();
-        diagnostics.push(Diagnostic {
-            range: Range {
-                start: Position {
-                    character: lexr.position.line as u32,
-                    line: lexr.position.column as u32,
-                },
-                end: Position {
-                    character: (lexr.position.line + 1) as u32,
-                    line: lexr.position.column as u32,
-                },
-            },
-            severity: Some(DiagnosticSeverity::ERROR),
-            message: err_msg,
-            ..Default::default()
-        });
-        return diagnostics;
-    }
-
-    let mut parser = Parser::from_lexer(lexr);
+    // Lexer errors are reported by the parser along with its own
+    let mut parser = Parser::from_lexer(Lexer::from_source(&source));
+    let document = Document::new(String::new(), "jtl".to_string(), 0, source.clone());
     for err in parser.parse().errors {
         diagnostics.push(Diagnostic {
             range: Range {
-                start: Position {
-                    character: err.start.line as u32,
-                    line: err.start.column as u32,
-                },
-                end: Position {
-                    character: err.end.line as u32,
-                    line: err.end.column as u32,
-                },
+                start: document.to_lsp(err.start),
+                end: document.to_lsp(err.end),
             },
             severity: Some(DiagnosticSeverity::ERROR),
             message: err.message,
@@ -73,6 +48,22 @@ pub struct StructuresMidd(pub String, pub Vec<String>);
 /// SGlobal(key, value)
 pub struct SGlobal(pub String, pub String);
 
+#[derive(Clone, Debug)]
+/// SFunction - signature of a function, globals point to it with "@Name"
+pub struct SFunction {
+    pub description: Vec<String>,
+    /// Type of each argument, optional ones end with '?'. Aka: \["String", "Int?"]
+    pub arguments: Vec<String>,
+    pub return_type: String,
+}
+
+impl SFunction {
+    /// Aka: "(String, Int?) -> String"
+    pub fn signature(&self) -> String {
+        signature(&self.arguments, &self.return_type)
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct SchemaService {
     pub v: String,
@@ -84,64 +75,67 @@ pub struct SchemaService {
     ///
     pub structures: HashMap<String, Vec<StructuresMidd>>,
     // pub structures: Vec<StructuresUpper>,
-    // TODO:
-    // pub functions:
+    pub functions: HashMap<String, SFunction>,
+}
+
+impl SchemaService {
+    /// Adds the globals, structures and functions of `other`, aka: a library's schema
+    pub fn merge(&mut self, other: SchemaService) {
+        self.global.extend(other.global);
+        self.structures.extend(other.structures);
+        self.functions.extend(other.functions);
+    }
+
+    fn global_completion(&self, SGlobal(key, value): &SGlobal) -> CompletionItem {
+        let function = value
+            .strip_prefix('@')
+            .and_then(|name| self.functions.get(name));
+
+        match function {
+            Some(function) => CompletionItem {
+                label: key.clone(),
+                kind: Some(CompletionItemKind::FUNCTION),
+                detail: Some(function.signature()),
+                documentation: Some(Documentation::String(function.description.join("\n"))),
+                ..Default::default()
+            },
+            // TODO: set kind depending on 'value'
+            None => CompletionItem {
+                label: key.clone(),
+                kind: Some(CompletionItemKind::TEXT),
+                ..Default::default()
+            },
+        }
+    }
 }
 
 pub struct Service {}
 
 impl Service {
     pub fn do_diagnostic(document: Document, _schema: Option<SchemaService>) -> Vec<Diagnostic> {
-        let source = document.get_text();
         let mut diagnostics: Vec<Diagnostic> = Vec::new();
-        let ast_r = Service::diagnostic_parser(source, &mut diagnostics);
+        let ast_r = Service::diagnostic_parser(&document, &mut diagnostics);
 
         if ast_r.is_none() {
             return diagnostics;
         }
 
-        return diagnostics;
+        diagnostics
     }
 
     fn diagnostic_parser(
-        source: String,
+        document: &Document,
         diagnostics: &mut Vec<Diagnostic>,
     ) -> Option<ParserResults> {
-        let mut lexr = Lexer::from_source(&source);
-        let lexr_res = lexr.scan_tokens();
-        if lexr_res.is_err() {
-            let err_msg = lexr_res.err().unwrap();
-            diagnostics.push(Diagnostic {
-                range: Range {
-                    start: Position {
-                        character: lexr.position.line as u32,
-                        line: lexr.position.column as u32,
-                    },
-                    end: Position {
-                        character: (lexr.position.line + 1) as u32,
-                        line: lexr.position.column as u32,
-                    },
-                },
-                severity: Some(DiagnosticSeverity::ERROR),
-                message: err_msg,
-                ..Default::default()
-            });
-            return None;
-        }
-
-        let mut parser = Parser::from_lexer(lexr);
+        // Lexer errors are reported by the parser along with its own
+        let source = document.get_text();
+        let mut parser = Parser::from_lexer(Lexer::from_source(&source));
         let parse_results = parser.parse();
         for err in &parse_results.errors {
             diagnostics.push(Diagnostic {
                 range: Range {
-                    start: Position {
-                        character: err.start.line as u32,
-                        line: err.start.column as u32,
-                    },
-                    end: Position {
-                        character: err.end.line as u32,
-                        line: err.end.column as u32,
-                    },
+                    start: document.to_lsp(err.start),
+                    end: document.to_lsp(err.end),
                 },
                 severity: Some(DiagnosticSeverity::ERROR),
                 message: err.message.clone(),
@@ -149,7 +143,7 @@ impl Service {
             });
         }
 
-        return Some(parse_results);
+        Some(parse_results)
     }
 
     fn _diagnostic_types() {}
@@ -170,10 +164,8 @@ impl Service {
             };
         }
 
-        let offset = document.offset_at(jtl_parser::lex::PPosition {
-            column: position.line as usize,
-            line: position.character as usize,
-        });
+        let position = document.from_lsp(position);
+        let offset = document.offset_at(position);
 
         let ast_source = ast.unwrap();
         let node_res = get_node_at_offset(&mut document, offset, &ast_source);
@@ -191,16 +183,12 @@ impl Service {
 
         match node {
             Node::Expression => {
-                if schema.is_some() {
-                    let mut items: Vec<CompletionItem> = Vec::new();
-                    for SGlobal(key, _value) in schema.unwrap().global {
-                        // TODO: set kind depending on 'value'
-                        items.push(CompletionItem {
-                            label: key,
-                            kind: Some(CompletionItemKind::TEXT),
-                            ..Default::default()
-                        })
-                    }
+                if let Some(schema) = schema {
+                    let items: Vec<CompletionItem> = schema
+                        .global
+                        .iter()
+                        .map(|global| schema.global_completion(global))
+                        .collect();
 
                     CompletionList {
                         is_incomplete: false,
@@ -221,96 +209,44 @@ impl Service {
                         items: vec![],
                     };
                 };
-
                 let schema_safe = schema.unwrap();
-                let content_start = position.character - (property.start.line as u32) + 1;
-                let content_full = property.value.join(".");
-                // println!("content_full: {:?}", content_full);
-                // println!("content_start: {:?}", content_start);
-                let (content_complete, _): (&str, &str) = {
-                    if content_start as usize >= content_full.len() {
-                        (content_full.as_str(), "")
-                    } else {
-                        content_full.split_at(content_start as usize)
-                    }
-                };
 
-                // println!("content_complete: {:?}", content_complete);
-                let char_at =
-                    source.chars().collect::<Vec<_>>()[position.character as usize].to_string();
-                println!("char_at: {:?}", char_at);
-
-                // If this is true, then it should complete the final ident or None (no regex)
-                // Ex: "{guild.}" char position is on the '.'
-                let send_final_struct_full = char_at == ".";
-
-                let mut index: usize = 0;
-                let mut base_struct: Option<&Vec<StructuresMidd>> = None;
-                let content_split: std::str::Split<'_, &str> = content_complete.split(".");
-                let content_split_len = &content_split.clone().collect::<Vec<&str>>().len();
-                for c in content_split {
-                    index += 1;
-                    if &index == content_split_len {
-                        if send_final_struct_full {
-                            let mut items: Vec<CompletionItem> = Vec::new();
-                            for StructuresMidd(key, _value) in base_struct.unwrap() {
-                                // TODO: set kind depending on 'value'
-                                items.push(CompletionItem {
-                                    label: key.clone(),
-                                    kind: Some(CompletionItemKind::TEXT),
-                                    ..Default::default()
-                                })
-                            }
-
-                            return CompletionList {
-                                is_incomplete: false,
-                                items,
-                            };
-                        } else {
-                            println!("base_struct: {:?}", &base_struct);
-                            // TODO: Regex search
-                        }
-                        continue;
+                // What was typed of the property up to the cursor, "{guild.na|me}" -> "guild.na"
+                let property_start = document.offset_at(property.start) as usize;
+                let content_complete: String = source
+                    .chars()
+                    .skip(property_start)
+                    .take((offset as usize).saturating_sub(property_start))
+                    .collect();
+
+                // The last ident is the one being typed, filtering is left to the client
+                let mut path: Vec<&str> = content_complete.split('.').map(str::trim).collect();
+                path.pop();
+
+                let mut items: Vec<CompletionItem> = Vec::new();
+                if path.is_empty() {
+                    for global in &schema_safe.global {
+                        items.push(schema_safe.global_completion(global))
                     }
-                    // terverse though the schema... >
-
-                    // Top level terversal. Aka global, th rest should use structures...
-                    if index == 1 {
-                        let key: Option<String> = {
-                            let mut r = None;
-                            for SGlobal(key, value) in &schema_safe.global {
-                                if key == c {
-                                    r = Some(value.clone());
-                                    break;
-                                }
-                            }
-                            r
-                        };
-
-                        if key.is_some() {
-                            let key_safe = key.unwrap();
-                            if key_safe.starts_with("#") {
-                                let structure_res = &schema_safe.structures.get(&key_safe[1..]);
-                                if structure_res.is_none() {
-                                    return CompletionList {
-                                        is_incomplete: false,
-                                        items: vec![],
-                                    };
-                                }
-                                let structure = structure_res.unwrap();
-                                base_struct = Some(structure);
-                            }
-                        }
-                        continue;
+                } else if let Some(structure) = Service::structure_at_path(&schema_safe, &path) {
+                    // ":description" is about the structure itself
+                    let fields = structure
+                        .iter()
+                        .filter(|StructuresMidd(key, _)| !key.starts_with(':'));
+                    for StructuresMidd(key, _value) in fields {
+                        // TODO: set kind depending on 'value'
+                        items.push(CompletionItem {
+                            label: key.clone(),
+                            kind: Some(CompletionItemKind::TEXT),
+                            ..Default::default()
+                        })
                     }
-
-                    println!("If you've reached this message we currently doen't supported deeply nested object...\nFeel free to help - https://github.com/Wilbert-mad/jtl");
                 }
 
-                return CompletionList {
+                CompletionList {
                     is_incomplete: false,
-                    items: vec![],
-                };
+                    items,
+                }
             }
             Node::Text => CompletionList {
                 is_incomplete: false,
@@ -320,14 +256,35 @@ impl Service {
         }
     }
 
-    fn parse_ast(source: &String) -> Option<Source> {
-        let mut lexr = Lexer::from_source(&source);
-        let lexr_res = lexr.scan_tokens();
-        if lexr_res.is_err() {
-            return None;
+    /// Terverse the schema along a property path. Aka ["guild", "owner"] -> fields of "#User"
+    ///
+    /// The first key is looked up in the globals, the rest in the structures.
+    /// Types without a '#' (aka: "String") are looked up too, for the built-in properties of values.
+    fn structure_at_path<'s>(
+        schema: &'s SchemaService,
+        path: &[&str],
+    ) -> Option<&'s Vec<StructuresMidd>> {
+        let structure_of = |value_type: &str| {
+            // Optional values ("#User?") have the same fields
+            let value_type = value_type.strip_suffix('?').unwrap_or(value_type);
+            let name = value_type.strip_prefix('#').unwrap_or(value_type);
+            schema.structures.get(name)
+        };
+        let (first, rest) = path.split_first()?;
+        let SGlobal(_, global_type) = schema.global.iter().find(|SGlobal(key, _)| key == first)?;
+
+        let mut structure = structure_of(global_type)?;
+        for key in rest {
+            let StructuresMidd(_, value) =
+                structure.iter().find(|StructuresMidd(k, _)| k == key)?;
+            structure = structure_of(value.first()?)?;
         }
 
-        let mut parser = Parser::from_lexer(lexr);
+        Some(structure)
+    }
+
+    fn parse_ast(source: &str) -> Option<Source> {
+        let mut parser = Parser::from_lexer(Lexer::from_source(source));
         Some(parser.parse().ast)
     }
 }
@@ -352,11 +309,10 @@ mod tests {
     #[test]
     fn diagnostic_test() {
         let mut structures = HashMap::new();
-        let mut guild_struct = Vec::new();
-        guild_struct.push(StructuresMidd(
+        let guild_struct = vec![StructuresMidd(
             "name".to_string(),
             vec!["String".to_string()],
-        ));
+        )];
 
         structures.insert("Guild".to_string(), guild_struct);
 
@@ -365,6 +321,7 @@ mod tests {
             global: vec![SGlobal("guild".to_string(), "#Guild".to_string())],
             structures,
             // structures: vec![StructuresUpper("Guild".to_string(), guild_struct)],
+            functions: HashMap::new(),
         };
 
         let source = "start
@@ -391,4 +348,84 @@ mod tests {
             )
         );
     }
+
+    #[test]
+    fn autocomplete_half_typed_property() {
+        let mut structures = HashMap::new();
+        structures.insert(
+            "Guild".to_string(),
+            vec![
+                StructuresMidd("name".to_string(), vec!["String".to_string()]),
+                StructuresMidd("owner".to_string(), vec!["#User?".to_string()]),
+            ],
+        );
+        // Built-in properties of strings, as the runtime describes them
+        structures.insert(
+            "String".to_string(),
+            vec![StructuresMidd(
+                "length".to_string(),
+                vec!["Int".to_string()],
+            )],
+        );
+        structures.insert(
+            "User".to_string(),
+            vec![
+                StructuresMidd(
+                    ":description".to_string(),
+                    vec!["Someone on Discord".to_string()],
+                ),
+                StructuresMidd("mention".to_string(), vec!["String".to_string()]),
+            ],
+        );
+        let mut functions = HashMap::new();
+        functions.insert(
+            "ToPlacement".to_string(),
+            SFunction {
+                description: vec!["Converts number into placement".to_string()],
+                arguments: vec!["Int".to_string()],
+                return_type: "String".to_string(),
+            },
+        );
+        let schema = SchemaService {
+            v: "1.0.0".to_string(),
+            global: vec![
+                SGlobal("guild".to_string(), "#Guild".to_string()),
+                SGlobal("toPlacement".to_string(), "@ToPlacement".to_string()),
+            ],
+            structures,
+            functions,
+        };
+
+        let labels = |source: &str, character: u32| -> Vec<String> {
+            Service::do_autocomplete(
+                text_document_prop(source.to_string()),
+                Position { line: 0, character },
+                Some(schema.clone()),
+            )
+            .items
+            .into_iter()
+            .map(|item| item.label)
+            .collect()
+        };
+
+        assert_eq!(labels("Hi {guild.", 10), vec!["name", "owner"]);
+        assert_eq!(labels("Hi {guild.}", 10), vec!["name", "owner"]);
+        assert_eq!(labels("Hi {guild.owner.m}", 17), vec!["mention"]);
+        assert_eq!(labels("Hi {gu", 6), vec!["guild", "toPlacement"]);
+        assert_eq!(labels("Hi {guild.name.}", 15), vec!["length"]);
+        // The emoji is 2 UTF-16 code units, but a single character
+        assert_eq!(labels("🎉 {guild.owner.m}", 17), vec!["mention"]);
+
+        let items = Service::do_autocomplete(
+            text_document_prop("{to".to_string()),
+            Position {
+                line: 0,
+                character: 3,
+            },
+            Some(schema.clone()),
+        )
+        .items;
+        assert_eq!(items[1].kind, Some(CompletionItemKind::FUNCTION));
+        assert_eq!(items[1].detail.as_deref(), Some("(Int) -> String"));
+    }
 }
@@ -20,8 +20,8 @@ pub fn get_node_at_offset(document: &mut Document, offset: u32, program: &Source
     let child_node = child_at_offset(document, offset, &program.body);
     // println!("YYYY {:?}", &child_node);
     // println!("YYYY {:?} {:?}", offset, &program.body);
-    if child_node.is_some() {
-        return visit_child_node(document, offset, &child_node.unwrap());
+    if let Some(child_node) = child_node {
+        return visit_child_node(document, offset, &child_node);
     }
     None
 }
@@ -33,31 +33,34 @@ fn visit_child_node(document: &mut Document, offset: u32, node: &Stat) -> Option
             value: _,
             start: _,
             end: _,
+            span: _,
         } => Some(Node::Text),
         Stat::Tag {
             _type: _,
             start: _,
             end: _,
+            span: _,
             value,
         } => {
-            if value.property.is_none() {
+            // '{}' or '{"..."}', nothing typed yet that can be completed
+            let PValue::Property(property) = &value.property else {
                 return Some(Node::Expression);
-            }
-            let PValue::Property(property) = value.property.as_ref().unwrap()
-            else {todo!()};
+            };
 
             // Aka past the property and likey arguments
-            if offset > (document.offset_at(property.end.clone())) {
+            if offset > (document.offset_at(property.end)) {
                 return None;
             }
 
             let property_owned = (*property).clone();
             Some(Node::Property(property_owned))
         }
+        // Skipped tokens, nothing to complete
+        Stat::Error(_) => None,
     }
 }
 
-fn child_at_offset(document: &mut Document, offset: u32, children: &Vec<Stat>) -> Option<Stat> {
+fn child_at_offset(document: &mut Document, offset: u32, children: &[Stat]) -> Option<Stat> {
     let mut max = children.len() as i32 - 1i32;
     if max == -1 {
         return None;
@@ -73,17 +76,20 @@ fn child_at_offset(document: &mut Document, offset: u32, children: &Vec<Stat>) -
                 _type,
                 start,
                 end: _,
+                span: _,
                 value: _,
             } => start,
             Stat::Text {
                 _type,
                 start,
                 end: _,
+                span: _,
                 value: _,
             } => start,
+            Stat::Error(node) => &node.start,
         };
 
-        if (document.offset_at(start.clone()) as u32) < offset {
+        if document.offset_at(*start) < offset {
             min = mid;
         } else {
             max = mid - 1
@@ -96,14 +102,17 @@ fn child_at_offset(document: &mut Document, offset: u32, children: &Vec<Stat>) -
             _type,
             start,
             end,
+            span: _,
             value: _,
         } => (start, end),
         Stat::Text {
             _type,
             start,
             end,
+            span: _,
             value: _,
         } => (start, end),
+        Stat::Error(node) => (node.start, node.end),
     };
 
     // println!(
@@ -48,6 +48,7 @@ impl WASMLspSchema {
             v: self.get_version(),
             global: self.global.clone(),
             structures: self.structures.clone(),
+            functions: HashMap::new(),
         }
     }
 }